@@ -0,0 +1,114 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::env::Env;
+use crate::lisp_type::LispType;
+use crate::result::LispResult;
+
+// Constants a jump table can look up directly, keywords are symbols starting with ":"
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum CaseKey {
+    Int(i32),
+    Symbol(String),
+}
+
+impl CaseKey {
+    fn of(value: &LispType) -> Option<Self> {
+        match value {
+            LispType::Int(i) => Some(CaseKey::Int(*i)),
+            LispType::Symbol(s) => Some(CaseKey::Symbol(s.clone())),
+            _ => None,
+        }
+    }
+}
+
+// A (case expr constant result ... default) form with its jump table built once. Function
+// bodies hold these behind an Rc, so the copies made for every call share the table
+#[derive(Debug)]
+pub struct CaseForm {
+    pub form: Vec<LispType>,
+    table: HashMap<CaseKey, usize>, // Int and symbol constants to the first clause listing them
+    others: Vec<usize>, // Clauses listing constants of any other type, scanned in order
+}
+
+impl CaseForm {
+
+    pub fn new(form: Vec<LispType>) -> Self {
+
+        let mut table = HashMap::new();
+        let mut others = Vec::new();
+
+        for (index, clause) in form.get(2..).unwrap_or(&[]).chunks_exact(2).enumerate() {
+            let constants = match &clause[0] {
+                LispType::List(options, ..) => options.as_slice(),
+                constant => std::slice::from_ref(constant),
+            };
+            for constant in constants {
+                match CaseKey::of(constant) {
+                    Some(key) => { table.entry(key).or_insert(index); }
+                    None if others.last() != Some(&index) => others.push(index),
+                    None => {}
+                }
+            }
+        }
+
+        Self { form, table, others }
+
+    }
+
+    fn clause_matches(&self, index: usize, value: &LispType) -> bool {
+        match &self.form[2 + index * 2] {
+            LispType::List(options, ..) => options.iter().any(|option| option.equals(value)),
+            constant => constant.equals(value),
+        }
+    }
+
+    // Constants are compared unevaluated, a list of constants matches any of them
+    pub fn evaluate(&self, env: Rc<RefCell<Env>>) -> LispResult<LispType> {
+
+        if self.form.len() < 2 {
+            return Err(String::from("Expected at least 1 argument to \"case\" declaration"));
+        }
+
+        let value = self.form[1].evaluate(Rc::clone(&env))?;
+        let clauses = &self.form[2..];
+
+        // Other constants can still equal the value, like 1.0 and 1, so those before the hit are checked too
+        let found = match CaseKey::of(&value) {
+            Some(key) => {
+                let hit = self.table.get(&key).copied();
+                self.others.iter().copied()
+                    .take_while(|index| hit.is_none_or(|hit| *index < hit))
+                    .find(|index| self.clause_matches(*index, &value))
+                    .or(hit)
+            }
+            None => (0..clauses.len() / 2).find(|index| self.clause_matches(*index, &value)),
+        };
+
+        match found {
+            Some(index) => clauses[index * 2 + 1].evaluate(env),
+            None if clauses.len() % 2 == 1 => clauses.last().unwrap().evaluate(env),
+            None => Err(String::from("No matching clause in \"case\" declaration")),
+        }
+
+    }
+
+}
+
+// Replaces every (case ...) form inside form with a prepared one, done once when a function
+// or loop body is built rather than on every evaluation
+pub fn prepare(form: &LispType) -> LispType {
+    match form {
+        LispType::List(items, meta) => {
+            let items: Vec<LispType> = items.iter().map(prepare).collect();
+            if matches!(items.first(), Some(LispType::Symbol(s)) if s == "case") {
+                LispType::Case(Rc::new(CaseForm::new(items)))
+            } else {
+                LispType::List(items, meta.clone())
+            }
+        }
+        LispType::Vector(items) => LispType::Vector(items.iter().map(prepare).collect()),
+        other => other.clone(),
+    }
+}
@@ -0,0 +1,136 @@
+use std::cell::RefCell;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+use crate::env::Env;
+use crate::lisp_type::LispType;
+use crate::func::{LispFunc, check_arity, decl_native};
+use crate::reader::Reader;
+use crate::result::LispResult;
+use crate::string;
+
+// Input read from stdin but not used yet, shared so "read-line" picks up where "read" stopped
+type Pending = Rc<RefCell<String>>;
+
+pub fn init_console_funcs(env: Rc<RefCell<Env>>) {
+
+    let pending: Pending = Rc::new(RefCell::new(String::new()));
+
+    let input = Rc::clone(&pending);
+    decl_native!("read-line", move |args| read_line_native(args, &input), "()",
+        "Returns the next line of standard input without its line ending, nil at the end of input", env);
+    let input = Rc::clone(&pending);
+    let core_env = Rc::clone(&env);
+    decl_native!("read", move |args| read_native(args, &input, &core_env), "()",
+        "Reads the next form from standard input without evaluating it, nil at the end of input", env);
+    decl_native!("print", print_native, "(& xs)", "Prints xs as by str, separated by spaces, without a newline", env);
+    decl_native!("println", println_native, "(& xs)", "Prints xs as by str, separated by spaces, followed by a newline", env);
+    decl_native!("eprintln", eprintln_native, "(& xs)", "Prints xs as by str, separated by spaces, followed by a newline to standard error", env);
+    decl_native!("flush", flush_native, "()", "Writes out anything printed to standard output that is still buffered", env);
+
+}
+
+// Appends the next line of stdin to pending, false at the end of input. Anything printed
+// as a prompt is flushed first so it shows before blocking
+fn fill(pending: &Pending) -> LispResult<bool> {
+
+    io::stdout().flush().map_err(|err| format!("Failed to flush standard output: {}", err))?;
+
+    let read = io::stdin().lock().read_line(&mut pending.borrow_mut())
+        .map_err(|err| format!("Failed to read standard input: {}", err))?;
+
+    Ok(read > 0)
+
+}
+
+fn read_line_native(args: Vec<LispType>, pending: &Pending) -> LispResult<LispType> {
+
+    check_arity("read-line", &args, 0)?;
+
+    if !pending.borrow().contains('\n') && !fill(pending)? && pending.borrow().is_empty() {
+        return Ok(LispType::Nil);
+    }
+
+    let mut pending = pending.borrow_mut();
+    let end = pending.find('\n').map_or(pending.len(), |at| at + 1);
+    let line: String = pending.drain(..end).collect();
+
+    Ok(LispType::String(String::from(line.trim_end_matches(['\n', '\r']))))
+
+}
+
+// Keeps reading lines until they hold a whole form, the rest of the last line is left for the next read
+fn read_native(args: Vec<LispType>, pending: &Pending, env: &Rc<RefCell<Env>>) -> LispResult<LispType> {
+
+    check_arity("read", &args, 0)?;
+
+    loop {
+
+        let result = {
+            let input = pending.borrow();
+            let mut reader = Reader::new(&input).with_tag_readers(env.borrow().tag_readers());
+            let result = match reader.is_empty() {
+                Ok(true) => None,
+                Ok(false) => Some(reader.read_form()),
+                Err(msg) => Some(Err(msg)),
+            };
+            result.map(|result| (result, reader.offset(), input.len()))
+        };
+
+        let at_end = match result {
+            Some((Ok(form), offset, _)) => {
+                pending.borrow_mut().drain(..offset);
+                return Ok(form);
+            }
+            Some((Err(msg), offset, length)) if offset < length => {
+                pending.borrow_mut().clear();
+                return Err(msg);
+            }
+            Some((Err(msg), _, _)) => Some(msg),
+            None => None,
+        };
+
+        if !fill(pending)? {
+            pending.borrow_mut().clear();
+            return match at_end {
+                Some(msg) => Err(msg),
+                None => Ok(LispType::Nil),
+            };
+        }
+
+    }
+
+}
+
+// Like "str" except nil still prints as nil
+fn join_display(args: &[LispType]) -> String {
+    args.iter()
+        .map(|arg| if let LispType::Nil = arg { String::from("nil") } else { string::display(arg) })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn print_native(args: Vec<LispType>) -> LispResult<LispType> {
+    print!("{}", join_display(&args));
+    Ok(LispType::Nil)
+}
+
+fn println_native(args: Vec<LispType>) -> LispResult<LispType> {
+    println!("{}", join_display(&args));
+    Ok(LispType::Nil)
+}
+
+fn eprintln_native(args: Vec<LispType>) -> LispResult<LispType> {
+    eprintln!("{}", join_display(&args));
+    Ok(LispType::Nil)
+}
+
+fn flush_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("flush", &args, 0)?;
+
+    io::stdout().flush().map_err(|err| format!("Failed to flush standard output: {}", err))?;
+
+    Ok(LispType::Nil)
+
+}
@@ -0,0 +1,208 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::env::Env;
+use crate::lisp_type::LispType;
+use crate::result::LispResult;
+
+// Binds a pattern against a value in env, used by "def!", "let*" and function arguments.
+// A pattern is one of:
+//   name              binds the whole value
+//   (a (b c) & more)  binds list elements positionally, missing ones are nil, "&" takes the rest.
+//                     [a [b c] & more] is the same pattern written as a vector, and either matches lists and vectors
+//   (a &optional (b 1)) elements after "&optional" may be (pattern default), default is evaluated in env
+//   (a &key b (c 1))  the remaining values are a property list like (:b 2 :c 3), unknown keywords are errors
+//   (... :as all)     also binds the whole list
+//   (:keys a b)       looks up :a and :b in a map or a property list like (:a 1 :b 2)
+pub fn bind(env: &Rc<RefCell<Env>>, pattern: &LispType, value: LispType) -> LispResult<()> {
+
+    match pattern {
+        LispType::Symbol(s) => {
+            env.borrow_mut().set(s.as_str(), value);
+            Ok(())
+        }
+        LispType::List(patterns, ..) | LispType::Vector(patterns) => match patterns.first() {
+            Some(LispType::Symbol(s)) if s == ":keys" => bind_keys(env, &patterns[1..], value),
+            _ => bind_list(env, patterns, value),
+        },
+        _ => Err(String::from("Expected symbol, list or vector as binding pattern")),
+    }
+
+}
+
+pub fn bind_list(env: &Rc<RefCell<Env>>, patterns: &[LispType], value: LispType) -> LispResult<()> {
+
+    let values = match &value {
+        LispType::List(vec, ..) | LispType::Vector(vec) => vec.clone(),
+        LispType::Nil => Vec::new(),
+        _ => return Err(String::from("Expected list or vector value to destructure")),
+    };
+
+    let mut values = values.into_iter();
+    let mut optional = false;
+    let mut i = 0;
+
+    while i < patterns.len() {
+
+        match &patterns[i] {
+            LispType::Symbol(s) if s == "&" => {
+                let rest = patterns.get(i + 1).ok_or(String::from("Expected binding pattern after \"&\""))?;
+                bind(env, rest, LispType::List(values.by_ref().collect(), None))?;
+                i += 2;
+            }
+            LispType::Symbol(s) if s == ":as" => {
+                let all = patterns.get(i + 1).ok_or(String::from("Expected binding pattern after \":as\""))?;
+                bind(env, all, value.clone())?;
+                i += 2;
+            }
+            LispType::Symbol(s) if s == "&optional" => {
+                optional = true;
+                i += 1;
+            }
+            LispType::Symbol(s) if s == "&key" => {
+                return bind_key_args(env, &patterns[i + 1..], values.collect());
+            }
+            LispType::List(with_default, ..) if optional => {
+                if with_default.len() != 2 {
+                    return Err(String::from("Expected (pattern default) for \"&optional\" binding"));
+                }
+                let value = match values.next() {
+                    Some(value) => value,
+                    None => with_default[1].evaluate(Rc::clone(env))?,
+                };
+                bind(env, &with_default[0], value)?;
+                i += 1;
+            }
+            pattern => {
+                bind(env, pattern, values.next().unwrap_or(LispType::Nil))?;
+                i += 1;
+            }
+        }
+
+    }
+
+    Ok(())
+
+}
+
+fn bind_keys(env: &Rc<RefCell<Env>>, names: &[LispType], value: LispType) -> LispResult<()> {
+
+    let plist = match value {
+        LispType::List(vec, ..) => vec,
+        LispType::Map(map) => map.entries.into_iter().flat_map(|(key, value)| [key, value]).collect(),
+        LispType::Nil => Vec::new(),
+        _ => return Err(String::from("Expected map or property list value to destructure with \":keys\"")),
+    };
+
+    if plist.len() % 2 != 0 {
+        return Err(String::from("Found odd number of elements in property list destructured with \":keys\""));
+    }
+
+    for name in names {
+
+        let name = if let LispType::Symbol(s) = name {
+            s
+        } else {
+            return Err(String::from("Expected symbols after \":keys\" in binding pattern"));
+        };
+
+        let key = format!(":{}", name);
+        let found = plist.chunks(2)
+            .find(|pair| matches!(&pair[0], LispType::Symbol(s) if *s == key))
+            .map(|pair| pair[1].clone())
+            .unwrap_or(LispType::Nil);
+
+        env.borrow_mut().set(name.as_str(), found);
+
+    }
+
+    Ok(())
+
+}
+
+fn bind_key_args(env: &Rc<RefCell<Env>>, patterns: &[LispType], values: Vec<LispType>) -> LispResult<()> {
+
+    let mut names: Vec<&str> = Vec::with_capacity(patterns.len());
+    let mut defaults: Vec<Option<&LispType>> = Vec::with_capacity(patterns.len());
+
+    for pattern in patterns {
+        match pattern {
+            LispType::Symbol(s) => {
+                names.push(s.as_str());
+                defaults.push(None);
+            }
+            LispType::List(with_default, ..) if with_default.len() == 2 => match &with_default[0] {
+                LispType::Symbol(s) => {
+                    names.push(s.as_str());
+                    defaults.push(Some(&with_default[1]));
+                }
+                _ => return Err(String::from("Expected symbol for \"&key\" binding")),
+            },
+            _ => return Err(String::from("Expected symbol or (symbol default) for \"&key\" binding")),
+        }
+    }
+
+    let found = keyword_values(&values, &names)?;
+
+    for ((name, default), value) in names.iter().zip(defaults).zip(found) {
+        let value = match (value, default) {
+            (Some(value), _) => value,
+            (None, Some(default)) => default.evaluate(Rc::clone(env))?,
+            (None, None) => LispType::Nil,
+        };
+        env.borrow_mut().set(name, value);
+    }
+
+    Ok(())
+
+}
+
+// Matches keyword arguments like (:port 8080 :host "x") against the accepted names (without
+// the leading colon) and returns their values in the same order as names
+pub fn keyword_values(plist: &[LispType], names: &[&str]) -> LispResult<Vec<Option<LispType>>> {
+
+    if !plist.len().is_multiple_of(2) {
+        return Err(String::from("Found odd number of keyword arguments"));
+    }
+
+    let mut found: Vec<Option<LispType>> = vec![None; names.len()];
+
+    for pair in plist.chunks(2) {
+
+        let key = match &pair[0] {
+            LispType::Symbol(s) if s.starts_with(':') => &s[1..],
+            _ => return Err(String::from("Expected keyword in keyword arguments")),
+        };
+
+        match names.iter().position(|name| *name == key) {
+            Some(index) => found[index] = Some(pair[1].clone()),
+            None => return Err(format!("Unknown keyword argument :{}, expected one of :{}", key, names.join(" :"))),
+        }
+
+    }
+
+    Ok(found)
+
+}
+
+// Smallest and largest number of values a list pattern accepts, no largest if it takes a "&" rest
+pub fn arity(patterns: &[LispType]) -> (usize, Option<usize>) {
+
+    let mut required = 0;
+    let mut optional = 0;
+    let mut in_optional = false;
+    let mut iter = patterns.iter();
+
+    while let Some(pattern) = iter.next() {
+        match pattern {
+            LispType::Symbol(s) if s == "&" || s == "&key" => return (required, None),
+            LispType::Symbol(s) if s == ":as" => { iter.next(); }
+            LispType::Symbol(s) if s == "&optional" => in_optional = true,
+            _ if in_optional => optional += 1,
+            _ => required += 1,
+        }
+    }
+
+    (required, Some(required + optional))
+
+}
@@ -2,12 +2,16 @@
 
 use std::{collections::HashMap, rc::Rc, cell::RefCell};
 
-use crate::{lisp_type::LispType, result::LispResult};
+use crate::{lisp_type::LispType, module::{self, Modules, Namespace}, result::LispResult, tagged::TagReaders};
 
 #[derive(Clone)]
 pub struct Env {
     parent: Option<Rc<RefCell<Env>>>,
     data: HashMap<String, LispType>,
+    is_loop: bool, // Set for the frames created by "loop", "recur" is only valid under one
+    namespace: Option<Namespace>, // Set for the top-level env of the script and of each module
+    modules: Option<Rc<RefCell<Modules>>>, // Only set for the core env
+    tag_readers: Option<Rc<RefCell<TagReaders>>>, // Only set for the core env
 }
 
 impl Env {
@@ -16,14 +20,137 @@ impl Env {
         Self {
             parent,
             data: HashMap::new(),
+            is_loop: false,
+            namespace: None,
+            modules: None,
+            tag_readers: None,
         }
     }
 
+    pub fn new_loop(parent: Option<Rc<RefCell<Env>>>) -> Self {
+        let mut env = Self::new(parent);
+        env.is_loop = true;
+        env
+    }
+
+    pub fn in_loop(&self) -> bool {
+        if self.is_loop {
+            true
+        } else if let Some(parent) = &self.parent {
+            parent.borrow().in_loop()
+        } else {
+            false
+        }
+    }
+
+    pub fn new_namespace(parent: Option<Rc<RefCell<Env>>>, namespace: Namespace) -> Self {
+        let mut env = Self::new(parent);
+        env.namespace = Some(namespace);
+        env
+    }
+
+    // The root of every env, holding the builtins. The script and each module get a namespace
+    // env under it, so they share the builtins but never see each other's bindings
+    pub fn new_core(modules: Modules) -> Self {
+        let mut env = Self::new(None);
+        env.modules = Some(Rc::new(RefCell::new(modules)));
+        env.tag_readers = Some(Rc::new(RefCell::new(TagReaders::new())));
+        env
+    }
+
+    pub fn root(env: &Rc<RefCell<Env>>) -> Rc<RefCell<Env>> {
+        match &env.borrow().parent {
+            Some(parent) => Env::root(parent),
+            None => Rc::clone(env),
+        }
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.parent.is_none()
+    }
+
+    // The nearest enclosing env that has a namespace, which holds its aliases
+    pub fn namespace_env(env: &Rc<RefCell<Env>>) -> Option<Rc<RefCell<Env>>> {
+        if env.borrow().namespace.is_some() {
+            return Some(Rc::clone(env));
+        }
+        match &env.borrow().parent {
+            Some(parent) => Env::namespace_env(parent),
+            None => None,
+        }
+    }
+
+    pub fn namespace_mut(&mut self) -> Option<&mut Namespace> {
+        self.namespace.as_mut()
+    }
+
+    pub fn modules(&self) -> Option<Rc<RefCell<Modules>>> {
+        if let Some(modules) = &self.modules {
+            Some(Rc::clone(modules))
+        } else if let Some(parent) = &self.parent {
+            parent.borrow().modules()
+        } else {
+            None
+        }
+    }
+
+    pub fn tag_readers(&self) -> Option<Rc<RefCell<TagReaders>>> {
+        if let Some(tag_readers) = &self.tag_readers {
+            Some(Rc::clone(tag_readers))
+        } else if let Some(parent) = &self.parent {
+            parent.borrow().tag_readers()
+        } else {
+            None
+        }
+    }
+
+    fn find_alias(&self, alias: &str) -> Option<Rc<RefCell<Env>>> {
+        if let Some(namespace) = &self.namespace {
+            namespace.aliases.get(alias).cloned()
+        } else if let Some(parent) = &self.parent {
+            parent.borrow().find_alias(alias)
+        } else {
+            None
+        }
+    }
+
+    // Looks only at this env's own bindings, skipping private ones
+    pub fn find_public(&self, key: &str) -> Option<LispType> {
+        self.data.get(key).filter(|value| !module::is_private(value)).cloned()
+    }
+
+    pub fn public_bindings(&self) -> Vec<(String, LispType)> {
+        self.data.iter()
+            .filter(|(_, value)| !module::is_private(value))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
     pub fn set(&mut self, key: &str, value: LispType) {
         self.data.insert(String::from(key), value);
     }
 
+    // Updates the nearest binding of key, walking up through the parents. Builtins are shared by
+    // every namespace so they can only be shadowed, not assigned to
+    pub fn assign(&mut self, key: &str, value: LispType) -> LispResult<()> {
+        if self.is_root() && self.data.contains_key(key) {
+            Err(format!("Failed to assign to builtin \"{}\"", key))
+        } else if let Some(slot) = self.data.get_mut(key) {
+            *slot = value;
+            Ok(())
+        } else if let Some(parent) = &self.parent {
+            parent.borrow_mut().assign(key, value)
+        } else {
+            Err(format!("Failed to assign to unbound symbol \"{}\"", key))
+        }
+    }
+
     pub fn find(&self, key: &str) -> Option<LispType> {
+        if let Some((alias, name)) = module::split_qualified(key) {
+            if let Some(module_env) = self.find_alias(alias) {
+                return module_env.borrow().find_public(name);
+            }
+        }
         if let Some(value) = self.data.get(&String::from(key)) {
             Some(value.clone())
         } else {
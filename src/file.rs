@@ -0,0 +1,141 @@
+use std::cell::RefCell;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::destructure;
+use crate::env::Env;
+use crate::lisp_type::LispType;
+use crate::func::{LispFunc, check_arity, decl_native};
+use crate::result::LispResult;
+use crate::string;
+
+// Failures come back as errors naming the builtin, the path and the OS reason, nothing here panics
+pub fn init_file_funcs(env: Rc<RefCell<Env>>) {
+    decl_native!("slurp", slurp_native, "(path)", "Returns the contents of the file at path as a string", env);
+    decl_native!("spit", spit_native, "(path content & opts)",
+        "Writes content, printed as by str, to the file at path, replacing it unless :append is true", env);
+    decl_native!("read-lines", read_lines_native, "(path)", "Returns a list of the lines of the file at path, without line endings", env);
+    decl_native!("file-exists?", file_exists_native, "(path)", "Returns true if there is a file or directory at path", env);
+    decl_native!("dir?", is_dir_native, "(path)", "Returns true if there is a directory at path", env);
+    decl_native!("list-dir", list_dir_native, "(path)", "Returns a sorted list of the names of the entries in the directory at path", env);
+    decl_native!("make-dir", make_dir_native, "(path)", "Creates the directory at path along with any missing parents", env);
+    decl_native!("path-join", path_join_native, "(& parts)", "Returns the parts joined into one path, an absolute part replaces those before it", env);
+}
+
+fn expect_path<'a>(name: &str, args: &'a [LispType]) -> LispResult<&'a str> {
+    match &args[0] {
+        LispType::String(path) => Ok(path),
+        _ => Err(format!("First argument to \"{}\" is not a string", name)),
+    }
+}
+
+fn io_error(name: &str, path: &str, err: std::io::Error) -> String {
+    format!("Failed to \"{}\" \"{}\": {}", name, path, err)
+}
+
+fn slurp_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("slurp", &args, 1)?;
+
+    let path = expect_path("slurp", &args)?;
+
+    fs::read_to_string(path)
+        .map(LispType::String)
+        .map_err(|err| io_error("slurp", path, err))
+
+}
+
+fn spit_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    if args.len() < 2 {
+        return Err(format!("Expected at least 2 arguments to \"spit\", found {}", args.len()));
+    }
+
+    let path = expect_path("spit", &args)?;
+    let content = string::display(&args[1]);
+
+    let append = match destructure::keyword_values(&args[2..], &["append"])?.remove(0) {
+        Some(append) => append.is_truthy(),
+        None => false,
+    };
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)
+        .map_err(|err| io_error("spit", path, err))?;
+
+    file.write_all(content.as_bytes()).map_err(|err| io_error("spit", path, err))?;
+
+    Ok(LispType::Nil)
+
+}
+
+fn read_lines_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("read-lines", &args, 1)?;
+
+    let path = expect_path("read-lines", &args)?;
+    let input = fs::read_to_string(path).map_err(|err| io_error("read-lines", path, err))?;
+
+    Ok(LispType::List(input.lines().map(|line| LispType::String(String::from(line))).collect(), None))
+
+}
+
+fn file_exists_native(args: Vec<LispType>) -> LispResult<LispType> {
+    check_arity("file-exists?", &args, 1)?;
+    Ok(LispType::Bool(PathBuf::from(expect_path("file-exists?", &args)?).exists()))
+}
+
+fn is_dir_native(args: Vec<LispType>) -> LispResult<LispType> {
+    check_arity("dir?", &args, 1)?;
+    Ok(LispType::Bool(PathBuf::from(expect_path("dir?", &args)?).is_dir()))
+}
+
+fn list_dir_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("list-dir", &args, 1)?;
+
+    let path = expect_path("list-dir", &args)?;
+    let mut names: Vec<String> = Vec::new();
+
+    for entry in fs::read_dir(path).map_err(|err| io_error("list-dir", path, err))? {
+        let entry = entry.map_err(|err| io_error("list-dir", path, err))?;
+        names.push(entry.file_name().to_string_lossy().into_owned());
+    }
+
+    names.sort();
+
+    Ok(LispType::List(names.into_iter().map(LispType::String).collect(), None))
+
+}
+
+fn make_dir_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("make-dir", &args, 1)?;
+
+    let path = expect_path("make-dir", &args)?;
+    fs::create_dir_all(path).map_err(|err| io_error("make-dir", path, err))?;
+
+    Ok(LispType::Nil)
+
+}
+
+fn path_join_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    let mut path = PathBuf::new();
+
+    for part in &args {
+        match part {
+            LispType::String(part) => path.push(part),
+            _ => return Err(String::from("Expected string arguments to \"path-join\"")),
+        }
+    }
+
+    Ok(LispType::String(path.to_string_lossy().into_owned()))
+
+}
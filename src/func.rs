@@ -1,15 +1,23 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use crate::destructure;
 use crate::env::Env;
-use crate::lisp_type::LispType;
+use crate::lisp_type::{LispType, evaluate_body};
+use crate::map::LispMap;
+use crate::reader::Reader;
 use crate::result::LispResult;
 
-#[derive(Clone, Debug)]
+pub type NativeFunc = dyn Fn(Vec<LispType>) -> LispResult<LispType>;
+
+#[derive(Clone)]
 pub struct LispFunc {
-    pub body: Box<LispType>, // Expected to be a list that can be evaluated
-    pub args: Vec<LispType>, // Expected to be symbols
+    pub body: Vec<LispType>, // Evaluated in order, the last form is the result
+    pub meta: LispMap, // :name, :doc, :arglists and :private are used by the printer and "doc"
+    pub args: Vec<LispType>, // Binding patterns, see destructure::bind
     pub operator_func: Option<fn(LispType, LispType) -> LispResult<LispType>>,
+    pub native_func: Option<Rc<NativeFunc>>, // Receives already evaluated arguments, checks its own arity
+    pub arities: Vec<LispFunc>, // When not empty, calls go to the first of these that accepts the argument count
     pub internal_env: Rc<RefCell<Env>>,
 }
 
@@ -17,9 +25,12 @@ impl LispFunc {
 
     pub fn new(outer_env: Rc<RefCell<Env>>) -> Self {
         Self {
-            body: Box::new(LispType::List(Vec::new())), // temp
+            body: Vec::new(),
+            meta: LispMap::new(),
             args: Vec::new(),
             operator_func: None,
+            native_func: None,
+            arities: Vec::new(),
             internal_env: Rc::new(RefCell::new(Env::new(Some(outer_env)))),
         }
     }
@@ -31,28 +42,148 @@ impl LispFunc {
         func
     }
 
+    pub fn new_native(native_func: Rc<NativeFunc>) -> Self {
+        let mut func = Self::new(Rc::new(RefCell::new(Env::new(None))));
+        func.native_func = Some(native_func);
+        func
+    }
+
     pub fn call(&mut self, args: &[LispType], env: Rc<RefCell<Env>>) -> LispResult<LispType> {
 
-        if self.args.len() != args.len() {
-            return Err(format!("Expected {} arguments to function, found {}", self.args.len(), args.len()));
+        let mut evaluated: Vec<LispType> = Vec::with_capacity(args.len());
+
+        for arg in args {
+            evaluated.push(arg.evaluate(Rc::clone(&env))?);
+        }
+
+        self.call_evaluated(evaluated)
+
+    }
+
+    // Same as call but the arguments have already been evaluated, used by natives
+    // that need to call back into functions they were handed
+    pub fn call_evaluated(&self, args: Vec<LispType>) -> LispResult<LispType> {
+
+        if let Some(func) = &self.native_func {
+            return func(args);
+        }
+
+        if !self.arities.is_empty() {
+            return match self.arities.iter().find(|func| func.accepts(args.len())) {
+                Some(func) => func.call_evaluated(args),
+                None => {
+                    let labels: Vec<String> = self.arities.iter().map(|func| func.arity_label()).collect();
+                    let (last, rest) = labels.split_last().unwrap();
+                    let expected = if rest.is_empty() { last.clone() } else { format!("{} or {}", rest.join(", "), last) };
+                    Err(format!("Expected {} arguments to function, found {}", expected, args.len()))
+                }
+            };
+        }
+
+        if !self.accepts(args.len()) {
+            return Err(format!("Expected {} arguments to function, found {}", self.arity_label(), args.len()));
         }
 
         if let Some(func) = self.operator_func {
-            return func(args.first().unwrap().evaluate(Rc::clone(&env))?, args.last().unwrap().evaluate(Rc::clone(&env))?);
+            let mut iter = args.into_iter();
+            return func(iter.next().unwrap(), iter.next().unwrap());
         }
 
-        let mut env_cpy = self.internal_env.borrow().clone();
+        let env_cpy = Rc::new(RefCell::new(self.internal_env.borrow().clone()));
+
+        destructure::bind_list(&env_cpy, &self.args, LispType::List(args, None))?;
+
+        return evaluate_body(&self.body, env_cpy);
+
+    }
 
-        for i in 0..args.len() {
-            if let LispType::Symbol(s) = self.args.get(i).unwrap() {
-                env_cpy.set(s.as_str(), args.get(i).unwrap().evaluate(Rc::clone(&env))?);
-            } else {
-                return Err(String::from("Expected symbols as arguments in a function"));
-            }
+    pub fn name(&self) -> Option<String> {
+        match self.meta.get_keyword("name") {
+            Some(LispType::Symbol(s)) | Some(LispType::String(s)) => Some(s.clone()),
+            _ => None,
         }
-        
-        return self.body.evaluate(Rc::new(RefCell::new(env_cpy)));
+    }
 
+    // ((a b)), or ((a) (a b)) for a multi-arity function, the same shape "decl_native!" stores
+    pub fn arglists(&self) -> LispType {
+        let clauses = if self.arities.is_empty() { std::slice::from_ref(self) } else { self.arities.as_slice() };
+        LispType::List(clauses.iter().map(|clause| LispType::List(clause.args.clone(), None)).collect(), None)
     }
 
+    // Name, arglists and docstring as shown by "doc"
+    pub fn describe(&self) -> String {
+
+        let mut text = self.name().unwrap_or(String::from("<anonymous function>"));
+        if self.meta.get_keyword("private").is_some_and(|private| private.is_truthy()) {
+            text.push_str(" (private)");
+        }
+
+        let arglists = match self.meta.get_keyword("arglists") {
+            Some(arglists @ LispType::List(..)) => arglists.clone(),
+            _ => self.arglists(),
+        };
+        if let LispType::List(arglists, ..) = arglists {
+            text.push_str(&format!("\n{}", arglists.iter().map(LispType::to_string).collect::<Vec<String>>().join(" ")));
+        }
+
+        match self.meta.get_keyword("doc") {
+            Some(LispType::String(doc)) => text.push_str(&format!("\n  {}", doc)),
+            _ => text.push_str("\n  No documentation"),
+        }
+
+        text
+
+    }
+
+    fn accepts(&self, count: usize) -> bool {
+        let (required, max) = destructure::arity(&self.args);
+        count >= required && max.is_none_or(|max| count <= max)
+    }
+
+    // "2", "1-3" or "at least 2"
+    pub fn arity_label(&self) -> String {
+        match destructure::arity(&self.args) {
+            (required, Some(max)) if required == max => required.to_string(),
+            (required, Some(max)) => format!("{}-{}", required, max),
+            (required, None) => format!("at least {}", required),
+        }
+    }
+
+}
+
+// Reads the argument lists builtins are declared with, "(a) (a b)", into the list of lists
+// that :arglists holds
+pub fn read_arglists(text: &str) -> LispType {
+    let arglists = Reader::new(text).collect::<LispResult<Vec<LispType>>>().expect("Invalid argument lists for builtin");
+    LispType::List(arglists, None)
+}
+
+pub fn check_arity(name: &str, args: &[LispType], count: usize) -> LispResult<()> {
+    if args.len() != count {
+        return Err(format!("Expected {} argument{} to \"{}\", found {}", count, if count == 1 { "" } else { "s" }, name, args.len()));
+    }
+    Ok(())
+}
+
+macro_rules! decl_native {
+    ( $symbol:expr, $func:expr, $arglist:expr, $doc:expr, $env:expr ) => {
+        let mut func = LispFunc::new_native(Rc::new($func));
+        func.meta.insert_keyword("name", LispType::Symbol(String::from($symbol)));
+        func.meta.insert_keyword("arglists", $crate::func::read_arglists($arglist));
+        func.meta.insert_keyword("doc", LispType::String(String::from($doc)));
+        $env.borrow_mut().set($symbol, LispType::Func(func));
+    }
+}
+
+pub(crate) use decl_native;
+
+impl std::fmt::Debug for LispFunc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LispFunc")
+            .field("meta", &self.meta)
+            .field("body", &self.body)
+            .field("args", &self.args)
+            .field("native", &self.native_func.is_some())
+            .finish()
+    }
 }
@@ -0,0 +1,171 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::env::Env;
+use crate::lisp_type::LispType;
+use crate::func::{LispFunc, check_arity, decl_native};
+use crate::result::LispResult;
+
+pub fn init_functional_funcs(env: Rc<RefCell<Env>>) {
+    decl_native!("map", map_native, "(f xs & more)", "Calls f on each element of xs, or on elements of several lists at once until the shortest runs out", env);
+    decl_native!("filter", filter_native, "(pred xs)", "Returns the elements of xs for which pred is truthy", env);
+    decl_native!("reduce", reduce_native, "(f xs) (f init xs)", "Folds xs with f, starting from init or the first element", env);
+    decl_native!("apply", apply_native, "(f & args xs)", "Calls f with args followed by every element of xs", env);
+    decl_native!("partial", partial_native, "(f & args)", "Returns f with args bound in front of the arguments it is later called with", env);
+    decl_native!("comp", comp_native, "(& fs)", "Returns a function that calls fs from right to left, passing each result on", env);
+}
+
+fn expect_func(name: &str, value: LispType) -> LispResult<LispFunc> {
+    match value {
+        LispType::Func(func) => Ok(func),
+        _ => Err(format!("First argument to \"{}\" is not a function", name)),
+    }
+}
+
+fn expect_list(name: &str, value: LispType) -> LispResult<Vec<LispType>> {
+    match value {
+        LispType::List(vec, ..) | LispType::Vector(vec) => Ok(vec),
+        LispType::Nil => Ok(Vec::new()),
+        _ => Err(format!("Expected list or vector argument to \"{}\"", name)),
+    }
+}
+
+// (map f xs ys ...) stops at the end of the shortest list
+fn map_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    if args.len() < 2 {
+        return Err(format!("Expected at least 2 arguments to \"map\", found {}", args.len()));
+    }
+
+    let mut iter = args.into_iter();
+    let func = expect_func("map", iter.next().unwrap())?;
+
+    let mut lists: Vec<std::vec::IntoIter<LispType>> = Vec::new();
+    for list in iter {
+        lists.push(expect_list("map", list)?.into_iter());
+    }
+
+    let mut result: Vec<LispType> = Vec::new();
+
+    'outer: loop {
+        let mut call_args: Vec<LispType> = Vec::with_capacity(lists.len());
+        for list in lists.iter_mut() {
+            match list.next() {
+                Some(value) => call_args.push(value),
+                None => break 'outer,
+            }
+        }
+        result.push(func.call_evaluated(call_args)?);
+    }
+
+    Ok(LispType::List(result, None))
+
+}
+
+fn filter_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("filter", &args, 2)?;
+
+    let mut iter = args.into_iter();
+    let func = expect_func("filter", iter.next().unwrap())?;
+    let list = expect_list("filter", iter.next().unwrap())?;
+
+    let mut result: Vec<LispType> = Vec::new();
+
+    for item in list {
+        if func.call_evaluated(vec![item.clone()])?.is_truthy() {
+            result.push(item);
+        }
+    }
+
+    Ok(LispType::List(result, None))
+
+}
+
+// (reduce f xs) uses the first element as the initial value, (reduce f init xs) doesn't
+fn reduce_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    if args.len() != 2 && args.len() != 3 {
+        return Err(format!("Expected 2 or 3 arguments to \"reduce\", found {}", args.len()));
+    }
+
+    let has_init = args.len() == 3;
+    let mut iter = args.into_iter();
+    let func = expect_func("reduce", iter.next().unwrap())?;
+
+    let (mut acc, list) = if has_init {
+        let init = iter.next().unwrap();
+        (init, expect_list("reduce", iter.next().unwrap())?)
+    } else {
+        let mut list = expect_list("reduce", iter.next().unwrap())?;
+        if list.is_empty() {
+            return Err(String::from("Can't \"reduce\" an empty list without an initial value"));
+        }
+        (list.remove(0), list)
+    };
+
+    for item in list {
+        acc = func.call_evaluated(vec![acc, item])?;
+    }
+
+    Ok(acc)
+
+}
+
+// (apply f a b xs) calls f with a, b and then every element of xs
+fn apply_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    if args.len() < 2 {
+        return Err(format!("Expected at least 2 arguments to \"apply\", found {}", args.len()));
+    }
+
+    let mut args = args;
+    let func = expect_func("apply", args.remove(0))?;
+    let spread = expect_list("apply", args.pop().unwrap())?;
+    args.extend(spread);
+
+    func.call_evaluated(args)
+
+}
+
+fn partial_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    if args.is_empty() {
+        return Err(String::from("Expected at least 1 argument to \"partial\", found 0"));
+    }
+
+    let mut bound = args;
+    let func = expect_func("partial", bound.remove(0))?;
+
+    Ok(LispType::Func(LispFunc::new_native(Rc::new(move |args: Vec<LispType>| {
+        let mut all = bound.clone();
+        all.extend(args);
+        func.call_evaluated(all)
+    }))))
+
+}
+
+// (comp f g h) returns a function that calls h, then g, then f
+fn comp_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    let mut funcs: Vec<LispFunc> = Vec::with_capacity(args.len());
+    for value in args {
+        match value {
+            LispType::Func(func) => funcs.push(func),
+            _ => return Err(String::from("Expected only functions as arguments to \"comp\"")),
+        }
+    }
+
+    Ok(LispType::Func(LispFunc::new_native(Rc::new(move |args: Vec<LispType>| {
+        let mut iter = funcs.iter().rev();
+        let mut result = match iter.next() {
+            Some(func) => func.call_evaluated(args)?,
+            None => return args.into_iter().next().ok_or(String::from("Expected 1 argument to identity function, found 0")),
+        };
+        for func in iter {
+            result = func.call_evaluated(vec![result])?;
+        }
+        Ok(result)
+    }))))
+
+}
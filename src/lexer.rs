@@ -0,0 +1,214 @@
+use crate::result::LispResult;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokenKind {
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Quote,
+    Backquote,
+    Tilde,
+    TildeAt,
+    Caret,
+    At,
+    String, // Text includes the quotes and escapes as written
+    Char, // Text includes the backslash
+    Discard, // #_, the reader drops the form after it
+    Hash, // Any other #, the reader dispatches on the token right after it
+    Number,
+    Symbol,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Span {
+    pub start: usize, // Byte offsets into the input
+    pub end: usize,
+    pub line: usize, // 1-based, counted in chars
+    pub col: usize,
+}
+
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {} col {}", self.line, self.col)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str, // Borrowed from the input, tokens are never copied
+    pub span: Span,
+}
+
+// Single pass over the input that skips whitespace, commas and ; comments
+pub struct Lexer<'a> {
+    input: &'a str,
+    position: usize,
+    line: usize,
+    col: usize,
+}
+
+impl<'a> Lexer<'a> {
+
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            position: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek_char()?;
+        self.position += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_ignored(&mut self) -> LispResult<()> {
+        while let Some(c) = self.peek_char() {
+            if c.is_whitespace() || c == ',' {
+                self.bump();
+            } else if c == ';' {
+                while !matches!(self.peek_char(), None | Some('\n')) {
+                    self.bump();
+                }
+            } else if self.input[self.position..].starts_with("#|") {
+                self.skip_block_comment()?;
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // #| ... |# comments nest, so commenting out a section that already has one still works
+    fn skip_block_comment(&mut self) -> LispResult<()> {
+
+        let (line, col) = (self.line, self.col);
+        let mut depth = 0;
+
+        loop {
+            let rest = &self.input[self.position..];
+            if rest.starts_with("#|") {
+                depth += 1;
+            } else if rest.starts_with("|#") {
+                depth -= 1;
+            } else if self.bump().is_some() {
+                continue;
+            } else {
+                return Err(format!("Unterminated block comment starting at line {} col {}", line, col));
+            }
+            self.bump();
+            self.bump();
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+
+    }
+
+    fn is_delimiter(c: char) -> bool {
+        c.is_whitespace() || matches!(c, '[' | ']' | '{' | '}' | '(' | ')' | '\'' | '"' | '`' | ',' | ';')
+    }
+
+}
+
+impl<'a> Iterator for Lexer<'a> {
+
+    type Item = LispResult<Token<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+
+        if let Err(msg) = self.skip_ignored() {
+            return Some(Err(msg));
+        }
+
+        let (start, line, col) = (self.position, self.line, self.col);
+        let first = self.bump()?;
+
+        let kind = match first {
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            '{' => TokenKind::LBrace,
+            '}' => TokenKind::RBrace,
+            '[' => TokenKind::LBracket,
+            ']' => TokenKind::RBracket,
+            '\'' => TokenKind::Quote,
+            '`' => TokenKind::Backquote,
+            '^' => TokenKind::Caret,
+            '@' => TokenKind::At,
+            '~' if self.peek_char() == Some('@') => {
+                self.bump();
+                TokenKind::TildeAt
+            }
+            '~' => TokenKind::Tilde,
+            '#' if self.peek_char() == Some('_') => {
+                self.bump();
+                TokenKind::Discard
+            }
+            '#' => TokenKind::Hash,
+            // \( is a single character, \newline and \u03bb run up to the next delimiter
+            '\\' => {
+                match self.bump() {
+                    Some(c) if c.is_alphanumeric() => {
+                        while self.peek_char().is_some_and(|c| !Lexer::is_delimiter(c)) {
+                            self.bump();
+                        }
+                    }
+                    Some(_) => {}
+                    None => return Some(Err(format!("Expected character after \"\\\" at line {} col {}", line, col))),
+                }
+                TokenKind::Char
+            }
+            '"' => {
+                loop {
+                    match self.bump() {
+                        Some('"') => break,
+                        Some('\\') => { self.bump(); }
+                        Some(_) => {}
+                        None => return Some(Err(format!("Unterminated string starting at line {} col {}", line, col))),
+                    }
+                }
+                TokenKind::String
+            }
+            _ => {
+                while self.peek_char().is_some_and(|c| !Lexer::is_delimiter(c)) {
+                    self.bump();
+                }
+                let mut chars = self.input[start..self.position].chars();
+                let starts_number = match chars.next() {
+                    Some('-') => chars.next().is_some_and(|c| c.is_ascii_digit()),
+                    Some(c) => c.is_ascii_digit(),
+                    None => false,
+                };
+                if starts_number { TokenKind::Number } else { TokenKind::Symbol }
+            }
+        };
+
+        Some(Ok(Token {
+            kind,
+            text: &self.input[start..self.position],
+            span: Span { start, end: self.position, line, col },
+        }))
+
+    }
+
+}
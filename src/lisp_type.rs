@@ -3,20 +3,37 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use regex::Regex;
+
+use crate::case::{self, CaseForm};
+use crate::destructure;
 use crate::env::Env;
 use crate::result::LispResult;
 use crate::func::LispFunc;
+use crate::map::LispMap;
+use crate::set::LispSet;
+use crate::tagged::TaggedLiteral;
+use crate::module;
+use crate::reader::{CHAR_NAMES, STRING_ESCAPES};
 
 #[derive(Clone, Debug)]
 pub enum LispType {
     Func(LispFunc),
-    List(Vec<LispType>),
+    List(Vec<LispType>, Option<Box<LispMap>>), // Items and metadata, see "with-meta"
+    Vector(Vec<LispType>), // [a b], evaluates its items rather than being a call
+    Map(LispMap),
+    Set(LispSet),
     Int(i32),
     Float(f32),
     Symbol(String),
     String(String),
+    Char(char),
+    Regex(Rc<Regex>), // Compiled once when read, clones share it
+    Tagged(TaggedLiteral), // #tag form kept as data by "tagged-literal"
     Bool(bool),
     Nil,
+    Recur(Vec<LispType>), // Produced by "recur" and consumed by the enclosing "loop"
+    Case(Rc<CaseForm>), // A "case" form in a function or loop body, see case::prepare
 }
 
 impl LispType {
@@ -25,7 +42,7 @@ impl LispType {
 
         match self {
 
-            LispType::List(vec) => {
+            LispType::List(vec, ..) => {
 
                 if vec.is_empty() {
                     return Ok(LispType::Int(0));
@@ -43,25 +60,50 @@ impl LispType {
                                 return Err(String::from("Expected two arguments to \"def!\" declaration"));
                             }
 
-                            if let LispType::Symbol(to_def) = eval_vec.get(1).unwrap() {
+                            // (def! ^:private x ...) reads as (def! (with-meta x {...}) ...), the metadata goes on
+                            // the value where it can hold some and is dropped for anything else
+                            let mut value = eval_vec.get(2).unwrap().evaluate(Rc::clone(&env))?;
+                            let target = match eval_vec.get(1).unwrap() {
+                                LispType::List(with_meta, ..) if with_meta.len() == 3
+                                    && matches!(&with_meta[0], LispType::Symbol(s) if s == "with-meta")
+                                    && matches!(&with_meta[1], LispType::Symbol(_)) => {
+                                    if let LispType::Map(name_meta) = with_meta[2].evaluate(Rc::clone(&env))? {
+                                        value.add_meta(name_meta);
+                                    }
+                                    &with_meta[1]
+                                }
+                                target => target,
+                            };
+                            destructure::bind(&env, target, value.clone())?;
+                            return Ok(value);
+                            
+                        }
+
+                        "set!" => {
+
+                            if eval_vec.len() != 3 {
+                                return Err(String::from("Expected two arguments to \"set!\" declaration"));
+                            }
+
+                            if let LispType::Symbol(to_set) = eval_vec.get(1).unwrap() {
                                 let value = eval_vec.get(2).unwrap().evaluate(Rc::clone(&env))?;
-                                env.borrow_mut().set(to_def.as_str(), value.clone());
+                                env.borrow_mut().assign(to_set.as_str(), value.clone())?;
                                 return Ok(value);
                             } else {
-                                return Err(String::from("Failed to evaluate \"def!\" declaration"));
+                                return Err(String::from("Expected symbol as first argument to \"set!\" declaration"));
                             }
-                            
+
                         }
 
                         "let*" => {
 
-                            if eval_vec.len() != 3 {
-                                return Err(String::from("Expected two arguments to \"let*\" declaration"));
+                            if eval_vec.len() < 2 {
+                                return Err(String::from("Expected list of definitions as second argument to \"let*\" declaration"));
                             }
                             
                             let new_env = Rc::new(RefCell::new(Env::new(Some(env))));
 
-                            if let LispType::List(def_list) = eval_vec.get(1).unwrap() {
+                            if let LispType::List(def_list, ..) = eval_vec.get(1).unwrap() {
                                 
                                 let mut defs = def_list.clone();
 
@@ -69,17 +111,12 @@ impl LispType {
                                     return Err(String::from("Found odd number of arguments for definitions in \"let*\" statement"));
                                 }
 
-                                while defs.len() > 0 {
-
-                                    let symbol = if let LispType::Symbol(s) = defs.remove(0) {
-                                        s
-                                    } else {
-                                        return Err(String::from("Expected symbol for \"let*\" definition"));
-                                    };
+                                while !defs.is_empty() {
 
+                                    let pattern = defs.remove(0);
                                     let value = defs.remove(0).evaluate(Rc::clone(&new_env))?;
 
-                                    new_env.borrow_mut().set(symbol.as_str(), value);
+                                    destructure::bind(&new_env, &pattern, value)?;
 
                                 }
 
@@ -87,134 +124,170 @@ impl LispType {
                                 return Err(String::from("Expected list of definitions as second argument to \"let*\" declaration"));
                             }
 
-                            return eval_vec.get(2).unwrap().evaluate(new_env);
+                            return evaluate_body(&eval_vec[2..], new_env);
 
                         }
 
-                        "fn*" => {
+                        "loop" => {
 
-                            if eval_vec.len() != 3 {
-                                return Err(String::from("Expected two arguments to \"fn*\" declaration"));
+                            if eval_vec.len() < 2 {
+                                return Err(String::from("Expected at least 1 argument to \"loop\" declaration"));
                             }
 
-                            let arg_list = eval_vec.get(1).unwrap();
-                            let body_list = eval_vec.get(2).unwrap();
-                             
-                            let mut func = LispFunc::new(Rc::clone(&env)); 
-                            func.body = Box::new(body_list.clone());
-                            func.args = if let LispType::List(vec) = arg_list.clone() { vec } else {
-                                return Err(String::from("Arguments for function were not a list"));
+                            let bindings = if let LispType::List(bindings, ..) = eval_vec.get(1).unwrap() {
+                                bindings
+                            } else {
+                                return Err(String::from("Expected list of bindings as first argument to \"loop\" declaration"));
                             };
 
-                            Ok(LispType::Func(func))
+                            if bindings.len() % 2 != 0 {
+                                return Err(String::from("Found odd number of arguments for bindings in \"loop\" statement"));
+                            }
 
-                        }
+                            let body: Vec<LispType> = eval_vec[2..].iter().map(case::prepare).collect();
+                            let inits: Vec<LispType> = bindings.iter().skip(1).step_by(2).cloned().collect();
+                            check_recur_body(&inits, false, bindings.len() / 2)?;
+                            check_recur_body(&body, true, bindings.len() / 2)?;
 
-                        "prn" => {
+                            // The init forms aren't inside the loop, so they get a plain frame and only the
+                            // body runs in a loop frame where "recur" is allowed
+                            let mut names: Vec<String> = Vec::with_capacity(bindings.len() / 2);
+                            let init_env = Rc::new(RefCell::new(Env::new(Some(Rc::clone(&env)))));
 
-                            if eval_vec.len() != 2 {
-                                return Err(String::from("Expected 1 argument to \"prn\" declaration"));
+                            for pair in bindings.chunks(2) {
+                                let name = if let LispType::Symbol(s) = &pair[0] {
+                                    s
+                                } else {
+                                    return Err(String::from("Expected symbol for \"loop\" binding"));
+                                };
+                                let value = pair[1].evaluate(Rc::clone(&init_env))?;
+                                init_env.borrow_mut().set(name.as_str(), value);
+                                names.push(name.clone());
+                            }
+
+                            let mut loop_env = Rc::new(RefCell::new(Env::new_loop(Some(Rc::clone(&env)))));
+                            for name in &names {
+                                let value = init_env.borrow().get(name)?;
+                                loop_env.borrow_mut().set(name.as_str(), value);
+                            }
+
+                            // Each iteration gets a fresh frame so closures don't see later rebinds
+                            loop {
+                                match evaluate_body(&body, Rc::clone(&loop_env))? {
+                                    LispType::Recur(values) => {
+                                        loop_env = Rc::new(RefCell::new(Env::new_loop(Some(Rc::clone(&env)))));
+                                        for (name, value) in names.iter().zip(values) {
+                                            loop_env.borrow_mut().set(name.as_str(), value);
+                                        }
+                                    }
+                                    result => return Ok(result),
+                                }
                             }
 
-                            let eval = eval_vec.get(1).unwrap().evaluate(Rc::clone(&env))?;
-                            eval.println();
-                            
-                            Ok(LispType::Nil)
-                                                        
                         }
 
-                        "list" => {
+                        "recur" => {
 
-                            let mut list: Vec<LispType> = Vec::new();
+                            if !env.borrow().in_loop() {
+                                return Err(String::from("Found \"recur\" outside of a \"loop\""));
+                            }
 
+                            let mut values: Vec<LispType> = Vec::with_capacity(eval_vec.len() - 1);
                             for item in &eval_vec[1..] {
-                                list.push(item.clone());
+                                values.push(item.evaluate(Rc::clone(&env))?);
                             }
 
-                            Ok(LispType::List(list))
+                            Ok(LispType::Recur(values))
 
                         }
 
-                        "list?" => {
+                        "fn*" => {
 
-                            if eval_vec.len() != 2 {
-                                return Err(String::from("Expected 1 argument to \"list?\" declaration"));
-                            }
+                            Ok(LispType::Func(new_func_decl(&eval_vec[1..], env)?))
 
-                            if let LispType::List(_) = eval_vec.get(1).unwrap() {
-                                return Ok(LispType::Bool(true));
-                            } else {
-                                return Ok(LispType::Bool(false));
-                            }
-                            
                         }
 
-                        "empty?" => {
-                            
-                            if eval_vec.len() != 2 {
-                                return Err(String::from("Expected 1 argument to \"empty?\" declaration"));
+                        // (defn name "doc" (args) body...), "defn-" also marks the function as private.
+                        // The name can carry metadata for the function, as in (defn ^:test name ...)
+                        "defn" | "defn-" => {
+
+                            let (name, name_meta) = match eval_vec.get(1) {
+                                Some(LispType::Symbol(name)) => (name, None),
+                                Some(LispType::List(with_meta, ..)) if with_meta.len() == 3
+                                    && matches!(&with_meta[0], LispType::Symbol(s) if s == "with-meta") => match &with_meta[1] {
+                                    LispType::Symbol(name) => (name, Some(&with_meta[2])),
+                                    _ => return Err(format!("Expected name as first argument to \"{}\" declaration", symbol)),
+                                },
+                                _ => return Err(format!("Expected name as first argument to \"{}\" declaration", symbol)),
+                            };
+
+                            let mut decl = &eval_vec[2..];
+                            let mut doc = None;
+                            if let (Some(LispType::String(s)), true) = (decl.first(), decl.len() > 1) {
+                                doc = Some(s.clone());
+                                decl = &decl[1..];
                             }
 
-                            let list = if let LispType::List(vec) = eval_vec.get(1).unwrap() {
-                                vec
-                            } else {
-                                return Err(String::from("First argument to \"empty?\" declaration is not a list"));
-                            };
+                            let mut func = new_func_decl(decl, Rc::clone(&env))?;
+                            func.meta.insert_keyword("name", LispType::Symbol(name.clone()));
+                            func.meta.insert_keyword("arglists", func.arglists());
+                            if symbol == "defn-" {
+                                func.meta.insert_keyword("private", LispType::Bool(true));
+                            }
+                            if let Some(doc) = doc {
+                                func.meta.insert_keyword("doc", LispType::String(doc));
+                            }
+
+                            let mut value = LispType::Func(func);
+                            if let Some(name_meta) = name_meta {
+                                if let LispType::Map(name_meta) = name_meta.evaluate(Rc::clone(&env))? {
+                                    value.add_meta(name_meta);
+                                }
+                            }
 
-                            Ok(LispType::Bool(list.is_empty()))
+                            env.borrow_mut().set(name.as_str(), value.clone());
+                            Ok(value)
 
                         }
 
-                        "type?" => {
+                        "ns" => module::ns_form(&eval_vec[1..], env),
 
-                            if eval_vec.len() != 3 {
-                                return Err(String::from("Expected 2 arguments to \"type?\" declaration"));
-                            }
-                             
-                            return Ok(LispType::Bool(std::mem::discriminant(&eval_vec.get(1).unwrap().evaluate(Rc::clone(&env))?) == 
-                                std::mem::discriminant(&eval_vec.get(2).unwrap().evaluate(Rc::clone(&env))?)));
+                        "require" => module::require_form(&eval_vec[1..], env),
 
-                        }
+                        "prn" => {
 
-                        "count" => {
-                            
                             if eval_vec.len() != 2 {
-                                return Err(String::from("Expected 1 argument to \"count\" declaration"));
+                                return Err(String::from("Expected 1 argument to \"prn\" declaration"));
                             }
 
-                            let list = if let LispType::List(vec) = eval_vec.get(1).unwrap() {
-                                vec
-                            } else {
-                                return Err(String::from("First argument to \"count\" declaration is not a list"));
-                            };
-
-                            Ok(LispType::Int(list.len() as i32))
-
+                            let eval = eval_vec.get(1).unwrap().evaluate(Rc::clone(&env))?;
+                            eval.println();
+                            
+                            Ok(LispType::Nil)
+                                                        
                         }
 
-                        "do" => {
+                        "type?" => {
 
-                            for item in &eval_vec[1..(eval_vec.len()-1)] {
-                                item.clone().evaluate(Rc::clone(&env))?;
+                            if eval_vec.len() != 3 {
+                                return Err(String::from("Expected 2 arguments to \"type?\" declaration"));
                             }
-
-                            eval_vec.last().unwrap().evaluate(Rc::clone(&env))
+                             
+                            return Ok(LispType::Bool(std::mem::discriminant(&eval_vec.get(1).unwrap().evaluate(Rc::clone(&env))?) == 
+                                std::mem::discriminant(&eval_vec.get(2).unwrap().evaluate(Rc::clone(&env))?)));
 
                         }
 
+                        "do" => evaluate_body(&eval_vec[1..], env),
+
                         "if" => {
                             if eval_vec.len() != 3 && eval_vec.len() != 4 {
                                 return Err(String::from("Expected 2 or 3 arguments to \"if\" declaration"));
                             }
 
                             let eval = eval_vec.get(1).unwrap().evaluate(Rc::clone(&env))?;
-                            let result = match eval {
-                                LispType::Nil => false,
-                                LispType::Bool(false) => false,
-                                _ => true,
-                            };
 
-                            if result {
+                            if eval.is_truthy() {
                                 return eval_vec.get(2).unwrap().evaluate(Rc::clone(&env));
                             } else {
                                 if eval_vec.len() == 4 {
@@ -226,51 +299,78 @@ impl LispType {
 
                         }
 
-                        "and" | "&&" => {
+                        "cond" => {
 
-                            if eval_vec.len() != 3 {
-                                return Err(String::from("Expected 2 arguments to \"and\" declaration"));
+                            if eval_vec.len() % 2 != 1 {
+                                return Err(String::from("Expected an even number of arguments to \"cond\" declaration"));
                             }
 
-                            let a = if let LispType::Bool(val) = eval_vec.get(1).unwrap().evaluate(Rc::clone(&env))? {
-                                val
-                            } else {
-                                return Err(String::from("First argument to \"and\" declaration is not boolean"));
-                            };
+                            for clause in eval_vec[1..].chunks(2) {
+                                let matched = match &clause[0] {
+                                    LispType::Symbol(s) if s == ":else" => true,
+                                    test => test.evaluate(Rc::clone(&env))?.is_truthy(),
+                                };
+                                if matched {
+                                    return clause[1].evaluate(Rc::clone(&env));
+                                }
+                            }
 
-                            let b = if let LispType::Bool(val) = eval_vec.get(2).unwrap().evaluate(Rc::clone(&env))? {
-                                val
-                            } else {
-                                return Err(String::from("Second argument to \"and\" declaration is not boolean"));
-                            };
+                            Ok(LispType::Nil)
 
-                            Ok(LispType::Bool(a && b))
-                        
                         }
 
-                        "or" | "||" => {
+                        // Only reached for forms outside a function or loop body, which run once
+                        "case" => CaseForm::new(eval_vec).evaluate(env),
 
-                            if eval_vec.len() != 3 {
-                                return Err(String::from("Expected 2 arguments to \"or\" declaration"));
+                        "when" | "unless" => {
+
+                            if eval_vec.len() < 2 {
+                                return Err(format!("Expected at least 1 argument to \"{}\" declaration", symbol));
                             }
 
-                            let a = if let LispType::Bool(val) = eval_vec.get(1).unwrap().evaluate(Rc::clone(&env))? {
-                                val
-                            } else {
-                                return Err(String::from("First argument to \"or\" declaration is not boolean"));
-                            };
+                            let eval = eval_vec.get(1).unwrap().evaluate(Rc::clone(&env))?;
 
-                            let b = if let LispType::Bool(val) = eval_vec.get(2).unwrap().evaluate(Rc::clone(&env))? {
-                                val
-                            } else {
-                                return Err(String::from("Second argument to \"or\" declaration is not boolean"));
-                            };
+                            if eval.is_truthy() == (symbol == "when") {
+                                return evaluate_body(&eval_vec[2..], env);
+                            }
+
+                            Ok(LispType::Nil)
+
+                        }
+
+                        "and" | "&&" => {
 
-                            Ok(LispType::Bool(a || b))
+                            // Returns the first falsy value, or the last value if all are truthy
+                            let mut result = LispType::Bool(true);
+
+                            for item in &eval_vec[1..] {
+                                result = item.evaluate(Rc::clone(&env))?;
+                                if !result.is_truthy() {
+                                    break;
+                                }
+                            }
+
+                            Ok(result)
+                        
+                        }
+
+                        "or" | "||" => {
+
+                            // Returns the first truthy value, or the last value if none are
+                            let mut result = LispType::Nil;
+
+                            for item in &eval_vec[1..] {
+                                result = item.evaluate(Rc::clone(&env))?;
+                                if result.is_truthy() {
+                                    break;
+                                }
+                            }
+
+                            Ok(result)
                         
                         }
 
-                        "xor" | "^" => {
+                        "xor" => {
 
                             if eval_vec.len() != 3 {
                                 return Err(String::from("Expected 2 arguments to \"xor\" declaration"));
@@ -345,46 +445,336 @@ impl LispType {
             },
 
             t @ LispType::String(_) => return Ok(t.clone()),
+            t @ LispType::Char(_) => return Ok(t.clone()),
+            t @ LispType::Regex(_) => return Ok(t.clone()),
+            t @ LispType::Tagged(_) => return Ok(t.clone()),
             t @ LispType::Bool(_) => return Ok(t.clone()),
             t @ LispType::Int(_) => return Ok(t.clone()),
             t @ LispType::Float(_) => return Ok(t.clone()),
             t @ LispType::Nil => return Ok(t.clone()),
+            LispType::Map(map) => {
+                let mut evaluated = LispMap::new();
+                for (key, value) in &map.entries {
+                    evaluated.insert(key.evaluate(Rc::clone(&env))?, value.evaluate(Rc::clone(&env))?);
+                }
+                evaluated.meta = map.meta.clone();
+                return Ok(LispType::Map(evaluated));
+            }
+            LispType::Set(set) => {
+                let mut evaluated = LispSet::new();
+                for item in &set.items {
+                    evaluated.insert(item.evaluate(Rc::clone(&env))?);
+                }
+                return Ok(LispType::Set(evaluated));
+            }
+            LispType::Vector(vec) => {
+                let mut evaluated = Vec::with_capacity(vec.len());
+                for item in vec {
+                    evaluated.push(item.evaluate(Rc::clone(&env))?);
+                }
+                return Ok(LispType::Vector(evaluated));
+            }
+
+            t @ LispType::Func(_) => return Ok(t.clone()),
+            t @ LispType::Recur(_) => return Ok(t.clone()),
+
+            // A function named "case" in scope takes over, the same as for an unprepared form
+            LispType::Case(case) => if env.borrow().find("case").is_some() {
+                LispType::List(case.form.clone(), None).evaluate(env)
+            } else {
+                case.evaluate(env)
+            },
+
+        }
+
+    }
 
-            _ => Err(String::from("Unhandled evaluation value")),
+    // Merges entries into the metadata of functions, maps and lists, other values have nowhere to keep it
+    pub fn add_meta(&mut self, entries: LispMap) {
 
+        let meta = match self {
+            LispType::Func(func) => &mut func.meta,
+            LispType::Map(map) => map.meta.get_or_insert_with(|| Box::new(LispMap::new())),
+            LispType::List(_, meta) => meta.get_or_insert_with(|| Box::new(LispMap::new())),
+            _ => return,
+        };
+
+        for (key, value) in entries.entries {
+            meta.insert(key, value);
+        }
+
+    }
+
+    // Structural equality, functions and regexes are never equal to anything
+    pub fn equals(&self, other: &LispType) -> bool {
+        match (self, other) {
+            (LispType::List(a, ..), LispType::List(b, ..)) | (LispType::Vector(a), LispType::Vector(b)) =>
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.equals(y)),
+            (LispType::Int(a), LispType::Int(b)) => a == b,
+            (LispType::Float(a), LispType::Float(b)) => a == b,
+            (LispType::Int(a), LispType::Float(b)) | (LispType::Float(b), LispType::Int(a)) => *a as f32 == *b,
+            (LispType::Symbol(a), LispType::Symbol(b)) => a == b,
+            (LispType::String(a), LispType::String(b)) => a == b,
+            (LispType::Char(a), LispType::Char(b)) => a == b,
+            (LispType::Bool(a), LispType::Bool(b)) => a == b,
+            (LispType::Nil, LispType::Nil) => true,
+            (LispType::Map(a), LispType::Map(b)) => a.equals(b),
+            (LispType::Set(a), LispType::Set(b)) => a.equals(b),
+            (LispType::Tagged(a), LispType::Tagged(b)) => a.tag == b.tag && a.form.equals(&b.form),
+            _ => false,
         }
+    }
 
+    // Only nil and false are falsy, matching "if"
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, LispType::Nil | LispType::Bool(false))
     }
 
     pub fn print(&self) {
+        print!("{}", self);
+    }
+    
+    pub fn println(&self) {
+        self.print();
+        println!();
+    }
+
+}
+
+impl std::fmt::Display for LispType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            LispType::List(vec) => {
-                let mut data = vec.clone();
-                print!("(");
-                for _ in 0..(data.len()) {
-                    data.remove(0).print();
-                    if data.len() != 0 {
-                        print!(" ");
+            LispType::List(vec, ..) => {
+                write!(f, "(")?;
+                for (i, item) in vec.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, " ")?;
                     }
+                    write!(f, "{}", item)?;
                 }
-                print!(")");
+                write!(f, ")")
+            },
+            LispType::Vector(vec) => {
+                write!(f, "[")?;
+                for (i, item) in vec.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            },
+            LispType::Int(i) => write!(f, "{}", i),
+            LispType::Float(fl) => write!(f, "{}", fl),
+            LispType::Symbol(s) => write!(f, "{}", s),
+            LispType::String(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    match STRING_ESCAPES.iter().find(|(_, unescaped)| *unescaped == c) {
+                        Some((name, _)) => write!(f, "\\{}", name)?,
+                        None => write!(f, "{}", c)?,
+                    }
+                }
+                write!(f, "\"")
+            },
+            LispType::Char(c) => match CHAR_NAMES.iter().find(|(_, named)| named == c) {
+                Some((name, _)) => write!(f, "\\{}", name),
+                None => write!(f, "\\{}", c),
+            },
+            LispType::Bool(b) => write!(f, "{}", b),
+            LispType::Nil => write!(f, "nil"),
+            LispType::Map(map) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in map.entries.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{} {}", key, value)?;
+                }
+                write!(f, "}}")
+            },
+            LispType::Set(set) => {
+                write!(f, "#{{")?;
+                for (i, item) in set.items.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "}}")
+            },
+            LispType::Regex(regex) => write!(f, "#\"{}\"", regex.as_str()),
+            LispType::Tagged(tagged) => write!(f, "#{} {}", tagged.tag, tagged.form),
+            LispType::Func(func) => match func.name() {
+                Some(name) => write!(f, "#<function {}>", name),
+                None => write!(f, "#<function>"),
             },
-            LispType::Int(i) => print!("{}", i),
-            LispType::Float(f) => print!("{}", f),
-            LispType::Symbol(s) => print!("{}", s),
-            LispType::String(s) => print!("\"{}\"", s),
-            LispType::Bool(b) => match *b {
-                true => print!("true"),
-                false => print!("false"),
+            LispType::Recur(_) => write!(f, "#<recur>"),
+            LispType::Case(case) => write!(f, "{}", LispType::List(case.form.clone(), None)),
+        }
+    }
+}
+
+// Builds a function from everything after "fn*", either (args body...) or several
+// ([args] body...) clauses for a multi-arity function. The arguments of a single-arity function
+// are a vector or a list, those of a clause are always a vector, so:
+// - if the first element is a list starting with a vector the function is multi-arity and every
+//   element has to be such a clause
+// - otherwise the first element is the argument list of a single-arity function
+// A single-arity function whose arguments are a list starting with a vector pattern has to be
+// written with a vector, as in (fn* [[a b] c] ...)
+fn new_func_decl(decl: &[LispType], env: Rc<RefCell<Env>>) -> LispResult<LispFunc> {
+
+    let is_clause = |clause: &LispType| matches!(clause, LispType::List(clause, ..) if matches!(clause.first(), Some(LispType::Vector(_))));
+
+    if !decl.first().is_some_and(is_clause) {
+        return new_func(decl, env);
+    }
+
+    let mut func = LispFunc::new(Rc::clone(&env));
+    for clause in decl {
+        let clause = match clause {
+            LispType::List(items, ..) if is_clause(clause) => new_func(items, Rc::clone(&env))?,
+            _ => return Err(String::from("Expected ([args] body...) clauses in multi-arity \"fn*\" declaration")),
+        };
+        // A clause that accepts a count an earlier one already takes could never be called with it
+        let (min, max) = destructure::arity(&clause.args);
+        let overlaps = |other: &LispFunc| {
+            let (other_min, other_max) = destructure::arity(&other.args);
+            max.is_none_or(|max| other_min <= max) && other_max.is_none_or(|other_max| min <= other_max)
+        };
+        if let Some(other) = func.arities.iter().find(|other| overlaps(other)) {
+            return Err(format!("Found clauses taking {} and {} arguments in multi-arity \"fn*\" declaration, which overlap",
+                other.arity_label(), clause.arity_label()));
+        }
+        func.arities.push(clause);
+    }
+    if let Some(doc) = func.arities.iter().find_map(|clause| clause.meta.get_keyword("doc").cloned()) {
+        func.meta.insert_keyword("doc", doc);
+    }
+
+    Ok(func)
+
+}
+
+// Builds a function from the (args body...) part of a "fn*" declaration, a string
+// before the rest of the body is taken as its docstring
+fn new_func(decl: &[LispType], env: Rc<RefCell<Env>>) -> LispResult<LispFunc> {
+
+    if decl.is_empty() {
+        return Err(String::from("Expected argument list in \"fn*\" declaration"));
+    }
+
+    let mut func = LispFunc::new(env);
+    func.args = match &decl[0] {
+        LispType::List(vec, ..) | LispType::Vector(vec) => vec.clone(),
+        _ => return Err(String::from("Arguments for function were not a list or vector")),
+    };
+
+    let mut body = &decl[1..];
+    if let (Some(LispType::String(doc)), true) = (body.first(), body.len() > 1) {
+        func.meta.insert_keyword("doc", LispType::String(doc.clone()));
+        body = &body[1..];
+    }
+    func.body = body.iter().map(case::prepare).collect();
+
+    Ok(func)
+
+}
+
+// Evaluates every form in order and returns the last result, nil if there are none
+pub fn evaluate_body(body: &[LispType], env: Rc<RefCell<Env>>) -> LispResult<LispType> {
+
+    let mut result = LispType::Nil;
+
+    for item in body {
+        result = item.evaluate(Rc::clone(&env))?;
+    }
+
+    Ok(result)
+
+}
+
+// Checks a "loop" body before running it so that a "recur" that isn't in tail position
+// is reported up front instead of leaking out of the middle of an expression
+fn check_recur(form: &LispType, tail: bool, arity: usize) -> LispResult<()> {
+
+    let list = match form {
+        LispType::List(list, ..) if !list.is_empty() => list,
+        LispType::Case(case) => &case.form,
+        _ => return Ok(()),
+    };
+
+    let head = match list.first() {
+        Some(LispType::Symbol(s)) => s.as_str(),
+        _ => "",
+    };
+
+    let check_all = |forms: &[LispType]| -> LispResult<()> {
+        for item in forms {
+            check_recur(item, false, arity)?;
+        }
+        Ok(())
+    };
+
+    match head {
+        "recur" => {
+            if !tail {
+                return Err(String::from("Found \"recur\" outside of tail position"));
+            }
+            if list.len() - 1 != arity {
+                return Err(format!("Expected {} arguments to \"recur\", found {}", arity, list.len() - 1));
+            }
+            check_all(&list[1..])
+        }
+        "loop" => Ok(()), // Checked on its own when it's evaluated
+        "do" => check_recur_body(&list[1..], tail, arity),
+        "if" => {
+            check_all(&list[1..list.len().min(2)])?;
+            for item in list.iter().skip(2) {
+                check_recur(item, tail, arity)?;
+            }
+            Ok(())
+        }
+        "when" | "unless" => {
+            check_all(&list[1..list.len().min(2)])?;
+            check_recur_body(list.get(2..).unwrap_or(&[]), tail, arity)
+        }
+        "cond" => {
+            for clause in list[1..].chunks(2) {
+                check_recur(&clause[0], false, arity)?;
+                if let Some(result) = clause.get(1) {
+                    check_recur(result, tail, arity)?;
+                }
             }
-            LispType::Nil => print!("nil"),
-            LispType::Func(_) => print!("#<function>"),
-        }   
+            Ok(())
+        }
+        "case" => {
+            check_all(&list[1..list.len().min(2)])?;
+            let clauses = list.get(2..).unwrap_or(&[]);
+            for clause in clauses.chunks(2) {
+                // A lone trailing form is the default result, otherwise the first one is a constant
+                check_recur(clause.last().unwrap(), tail, arity)?;
+            }
+            Ok(())
+        }
+        "let*" => {
+            if let Some(LispType::List(bindings, ..)) = list.get(1) {
+                check_all(bindings)?;
+            }
+            check_recur_body(list.get(2..).unwrap_or(&[]), tail, arity)
+        }
+        _ => check_all(&list[1..]),
     }
-    
-    pub fn println(&self) {
-        self.print();
-        println!();
+
+}
+
+fn check_recur_body(body: &[LispType], tail: bool, arity: usize) -> LispResult<()> {
+
+    for (i, item) in body.iter().enumerate() {
+        check_recur(item, tail && i == body.len() - 1, arity)?;
     }
 
+    Ok(())
+
 }
@@ -0,0 +1,64 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::env::Env;
+use crate::lisp_type::LispType;
+use crate::func::{LispFunc, check_arity, decl_native};
+use crate::result::LispResult;
+
+pub fn init_list_funcs(env: Rc<RefCell<Env>>) {
+    decl_native!("list", list_native, "(& items)", "Returns a list of its arguments", env);
+    decl_native!("list?", is_list_native, "(x)", "Returns true if x is a list", env);
+    decl_native!("vector", vector_native, "(& items)", "Returns a vector of its arguments", env);
+    decl_native!("vector?", is_vector_native, "(x)", "Returns true if x is a vector", env);
+    decl_native!("empty?", is_empty_native, "(xs)", "Returns true if xs is nil or a list, vector, map, set or string with no elements", env);
+    decl_native!("count", count_native, "(xs)", "Returns the number of elements in the list, vector or set xs, of entries in the map xs or of characters in the string xs, 0 for nil", env);
+}
+
+fn list_native(args: Vec<LispType>) -> LispResult<LispType> {
+    Ok(LispType::List(args, None))
+}
+
+fn is_list_native(args: Vec<LispType>) -> LispResult<LispType> {
+    check_arity("list?", &args, 1)?;
+    Ok(LispType::Bool(matches!(args[0], LispType::List(..))))
+}
+
+fn vector_native(args: Vec<LispType>) -> LispResult<LispType> {
+    Ok(LispType::Vector(args))
+}
+
+fn is_vector_native(args: Vec<LispType>) -> LispResult<LispType> {
+    check_arity("vector?", &args, 1)?;
+    Ok(LispType::Bool(matches!(args[0], LispType::Vector(_))))
+}
+
+fn is_empty_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("empty?", &args, 1)?;
+
+    match &args[0] {
+        LispType::List(vec, ..) | LispType::Vector(vec) => Ok(LispType::Bool(vec.is_empty())),
+        LispType::Map(map) => Ok(LispType::Bool(map.entries.is_empty())),
+        LispType::Set(set) => Ok(LispType::Bool(set.items.is_empty())),
+        LispType::String(s) => Ok(LispType::Bool(s.is_empty())),
+        LispType::Nil => Ok(LispType::Bool(true)),
+        _ => Err(String::from("First argument to \"empty?\" is not a list, vector, map, set or string")),
+    }
+
+}
+
+fn count_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("count", &args, 1)?;
+
+    match &args[0] {
+        LispType::List(vec, ..) | LispType::Vector(vec) => Ok(LispType::Int(vec.len() as i32)),
+        LispType::Map(map) => Ok(LispType::Int(map.entries.len() as i32)),
+        LispType::Set(set) => Ok(LispType::Int(set.items.len() as i32)),
+        LispType::String(s) => Ok(LispType::Int(s.chars().count() as i32)),
+        LispType::Nil => Ok(LispType::Int(0)),
+        _ => Err(String::from("First argument to \"count\" is not a list, vector, map, set or string")),
+    }
+
+}
@@ -0,0 +1,74 @@
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+
+use crate::env::Env;
+use crate::lisp_type::LispType;
+use crate::func::{LispFunc, check_arity, decl_native};
+use crate::reader::Reader;
+use crate::result::LispResult;
+
+// "eval" and "load-file" evaluate in user_env, the script's own namespace, so they never define into the core env
+pub fn init_loader_funcs(env: Rc<RefCell<Env>>, user_env: Rc<RefCell<Env>>) {
+    let eval_env = Rc::clone(&user_env);
+    decl_native!("eval", move |args| eval_native(args, Rc::clone(&eval_env)), "(form)", "Evaluates form in the script's namespace", env);
+    let eval_env = Rc::clone(&user_env);
+    decl_native!("load-file", move |args| load_file_native(args, Rc::clone(&eval_env)), "(path)",
+        "Reads and evaluates every form in the file at path in the script's namespace, returning the last result", env);
+    let core_env = Rc::clone(&env);
+    decl_native!("read-string", move |args| read_string_native(args, Rc::clone(&core_env)), "(s)", "Reads the form in s without evaluating it, nil if s is empty", env);
+}
+
+fn eval_native(args: Vec<LispType>, env: Rc<RefCell<Env>>) -> LispResult<LispType> {
+    check_arity("eval", &args, 1)?;
+    args[0].evaluate(env)
+}
+
+fn load_file_native(args: Vec<LispType>, env: Rc<RefCell<Env>>) -> LispResult<LispType> {
+
+    check_arity("load-file", &args, 1)?;
+
+    let path = match &args[0] {
+        LispType::String(path) => path,
+        _ => return Err(String::from("First argument to \"load-file\" is not a string")),
+    };
+
+    let input = fs::read_to_string(path).map_err(|err| format!("Failed to read file \"{}\": {}", path, err))?;
+
+    let reader = Reader::new(&input).with_tag_readers(env.borrow().tag_readers());
+
+    evaluate_reader(reader, env).map_err(|msg| format!("Failed to load file \"{}\": {}", path, msg))
+
+}
+
+// Evaluates each form as soon as it has been read, returning the last result
+pub fn evaluate_reader(reader: Reader, env: Rc<RefCell<Env>>) -> LispResult<LispType> {
+
+    let mut result = LispType::Nil;
+
+    for form in reader {
+        result = form?.evaluate(Rc::clone(&env))?;
+    }
+
+    Ok(result)
+
+}
+
+fn read_string_native(args: Vec<LispType>, env: Rc<RefCell<Env>>) -> LispResult<LispType> {
+
+    check_arity("read-string", &args, 1)?;
+
+    let input = match &args[0] {
+        LispType::String(input) => input,
+        _ => return Err(String::from("First argument to \"read-string\" is not a string")),
+    };
+
+    let mut reader = Reader::new(input).with_tag_readers(env.borrow().tag_readers());
+
+    if reader.is_empty()? {
+        return Ok(LispType::Nil);
+    }
+
+    reader.read_only_form()
+
+}
@@ -1,30 +1,66 @@
 #![allow(clippy::needless_return)] 
 
 mod result;
+mod case;
+mod console;
 mod operator;
+mod destructure;
 mod func;
+mod file;
+mod functional;
+mod list;
+mod loader;
+mod map;
+mod set;
+mod tagged;
+mod string;
+mod module;
+mod re;
 mod lisp_type;
 mod env;
 mod reader;
+mod lexer;
 
 use std::cell::RefCell;
 use std::process::exit;
 use std::{env as stdenv, fs};
+use std::path::PathBuf;
 use std::rc::Rc;
 
 use reader::Reader; 
 use env::Env;
+use lisp_type::LispType;
+use module::{Modules, Namespace};
 
 use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
 use colored::Colorize;
 
-fn jit() {
+// Builtins are declared in the core env and the script runs in the "user" namespace env under it.
+// Every init function must be given the core env, builtins such as "read" and "register-tag-reader"
+// hold on to the env they are declared in and rely on it being the core one
+fn new_global_env(search_paths: Vec<PathBuf>) -> Rc<RefCell<Env>> {
+    let core_env = Rc::new(RefCell::new(Env::new_core(Modules::new(search_paths))));
+    let global_env = Rc::new(RefCell::new(Env::new_namespace(Some(Rc::clone(&core_env)), Namespace::new("user"))));
+    operator::init_operator_funcs(Rc::clone(&core_env));
+    functional::init_functional_funcs(Rc::clone(&core_env));
+    list::init_list_funcs(Rc::clone(&core_env));
+    map::init_map_funcs(Rc::clone(&core_env));
+    set::init_set_funcs(Rc::clone(&core_env));
+    tagged::init_tagged_funcs(Rc::clone(&core_env));
+    string::init_string_funcs(Rc::clone(&core_env));
+    re::init_re_funcs(Rc::clone(&core_env));
+    loader::init_loader_funcs(Rc::clone(&core_env), Rc::clone(&global_env));
+    file::init_file_funcs(Rc::clone(&core_env));
+    console::init_console_funcs(Rc::clone(&core_env));
+    global_env
+}
+
+fn jit(search_paths: Vec<PathBuf>) {
 
     let mut rl = DefaultEditor::new().expect("Failed to load input / output");
 
-    let global_env = Rc::new(RefCell::new(Env::new(None)));
-    operator::init_operator_funcs(Rc::clone(&global_env));
+    let global_env = new_global_env(search_paths);
 
     loop {
 
@@ -42,88 +78,112 @@ fn jit() {
             continue;
         }
         
-        let mut reader = Reader::tokenize(input.trim_end().to_owned());
-        
-        if reader.tokens.is_empty() {
+        if let Some(name) = input.trim().strip_prefix(":doc ") {
+            match global_env.borrow().get(name.trim()) {
+                Ok(LispType::Func(func)) => println!("{}", func.describe()),
+                Ok(_) => println!("{} {}", "Doc error:".red().bold(), format!("\"{}\" is not a function", name.trim()).red()),
+                Err(msg) => println!("{} {}", "Doc error:".red().bold(), msg.as_str().red()),
+            }
             continue;
         }
+
+        let tag_readers = global_env.borrow().tag_readers();
+
+        for read in Reader::new(input.trim_end()).with_tag_readers(tag_readers) {
+
+            let form = match read {
+                Ok(v) => v,
+                Err(msg) => { println!("{} {}", "Reading error:".red().bold(), msg.as_str().red()); break },
+            };
+            
+            print!("{} ", "Parsed form:".green().bold());
+            form.println();
+            
+            #[cfg(debug_assertions)]
+            {
+                print!("{} \n{:#?}\n", "Parsed tree: ".green().bold(), form);
+            }
+
+            let eval = match form.evaluate(Rc::clone(&global_env)) {
+                Ok(v) => v,
+                Err(msg) => { println!("{} {}", "Evaluation error:".red().bold(), msg.as_str().red()); break },
+            };
+
+            print!("{} ", "Evaluation:".green().bold());
+            eval.println();
         
-        let form = match reader.read_form() {
+            #[cfg(debug_assertions)]
+            {
+                print!("{} \n{:#?}\n", "Evaluated tree: ".green().bold(), eval);
+            }
+
+        }
+
+    }
+
+
+}
+
+fn runtime(input: String, search_paths: Vec<PathBuf>) {
+    
+    let global_env = new_global_env(search_paths);
+
+    let tag_readers = global_env.borrow().tag_readers();
+
+    // Each form is evaluated before the next one is read
+    for read in Reader::new(&input).with_tag_readers(tag_readers) {
+
+        let form = match read {
             Ok(v) => v,
-            Err(msg) => { println!("{} {}", "Reading error:".red().bold(), msg.as_str().red()); continue },
+            Err(msg) => { println!("{} {}", "Reading error:".red().bold(), msg.as_str().red()); exit(1); },
         };
-        
-        print!("{} ", "Parsed form:".green().bold());
-        form.println();
-        
+
         #[cfg(debug_assertions)]
         {
             print!("{} \n{:#?}\n", "Parsed tree: ".green().bold(), form);
         }
 
-        let eval = match form.evaluate(Rc::clone(&global_env)) {
+        let _eval = match form.evaluate(Rc::clone(&global_env)) {
             Ok(v) => v,
-            Err(msg) => { println!("{} {}", "Evaluation error:".red().bold(), msg.as_str().red()); continue },
+            Err(msg) => { println!("{} {}", "Evaluation error:".red().bold(), msg.as_str().red()); exit(1); },
         };
 
-        print!("{} ", "Evaluation:".green().bold());
-        eval.println();
-    
         #[cfg(debug_assertions)]
         {
-            print!("{} \n{:#?}\n", "Evaluated tree: ".green().bold(), eval);
+            print!("{} \n{:#?}\n", "Evaluated tree: ".green().bold(), _eval);
         }
 
     }
-
-
-}
-
-fn runtime(input: String) {
-    
-    let global_env = Rc::new(RefCell::new(Env::new(None)));
-    operator::init_operator_funcs(Rc::clone(&global_env));
-
-    let mut reader = Reader::tokenize(input.trim_end().to_owned());
-        
-    if reader.tokens.is_empty() {
-        exit(0);
-    }
-    
-    let form = match reader.read_form() {
-        Ok(v) => v,
-        Err(msg) => { println!("{} {}", "Reading error:".red().bold(), msg.as_str().red()); exit(1); },
-    };
-
-    #[cfg(debug_assertions)]
-    {
-        print!("{} \n{:#?}\n", "Parsed tree: ".green().bold(), form);
-    }
-
-    let _eval = match form.evaluate(Rc::clone(&global_env)) {
-        Ok(v) => v,
-        Err(msg) => { println!("{} {}", "Evaluation error:".red().bold(), msg.as_str().red()); exit(1); },
-    };
-
-    #[cfg(debug_assertions)]
-    {
-        print!("{} \n{:#?}\n", "Evaluated tree: ".green().bold(), _eval);
-    }
     
     exit(0);
 
 }
 
+// lisp-rs [-I dir | --path dir]... [file]
 fn main() {
 
-    let args: Vec<String> = stdenv::args().collect();
+    let mut cli_paths: Vec<PathBuf> = Vec::new();
+    let mut files: Vec<String> = Vec::new();
+    let mut args = stdenv::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-I" | "--path" => match args.next() {
+                Some(dir) => cli_paths.push(PathBuf::from(dir)),
+                None => { println!("Expected directory after \"{}\"", arg); exit(1); }
+            },
+            _ => files.push(arg),
+        }
+    }
 
-    if args.len() ==  1 {
-        jit();
+    if files.is_empty() {
+        jit(module::search_paths(cli_paths));
         exit(0);
-    } else if args.len() == 2 {
+    } else if files.len() == 1 {
+
+        let file = files.remove(0);
 
-        let input = match fs::read_to_string(args.get(1).unwrap()) {
+        let input = match fs::read_to_string(&file) {
             Ok(raw) => match raw.parse::<String>() {
                 Ok(s) => s,
                 Err(_) => { println!("Failed to parse filename"); exit(1); }
@@ -131,7 +191,13 @@ fn main() {
             Err(_) => { println!("Failed to read file"); exit(1); }
         };
 
-        runtime(input);
+        // Modules next to the script are found without extra flags
+        let mut search_paths = module::search_paths(cli_paths);
+        if let Some(dir) = std::path::Path::new(&file).parent() {
+            search_paths.insert(search_paths.len() - 1, dir.to_path_buf());
+        }
+
+        runtime(input, search_paths);
 
         exit(0);
 
@@ -0,0 +1,160 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::env::Env;
+use crate::lisp_type::LispType;
+use crate::func::{LispFunc, check_arity, decl_native};
+use crate::result::LispResult;
+
+#[derive(Clone, Debug, Default)]
+pub struct LispMap {
+    pub entries: Vec<(LispType, LispType)>, // Kept in insertion order, keys compared with LispType::equals
+    pub meta: Option<Box<LispMap>>,
+}
+
+impl LispMap {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_pairs(items: Vec<LispType>) -> LispResult<Self> {
+
+        if !items.len().is_multiple_of(2) {
+            return Err(String::from("Expected an even number of forms in map"));
+        }
+
+        let mut map = Self::new();
+        let mut iter = items.into_iter();
+
+        while let (Some(key), Some(value)) = (iter.next(), iter.next()) {
+            map.insert(key, value);
+        }
+
+        Ok(map)
+
+    }
+
+    pub fn get(&self, key: &LispType) -> Option<&LispType> {
+        self.entries.iter().find(|(k, _)| k.equals(key)).map(|(_, v)| v)
+    }
+
+    // Looks up a keyword key given without its colon, get_keyword("doc") finds :doc
+    pub fn get_keyword(&self, key: &str) -> Option<&LispType> {
+        self.get(&LispType::Symbol(format!(":{}", key)))
+    }
+
+    pub fn insert(&mut self, key: LispType, value: LispType) {
+        match self.entries.iter_mut().find(|(k, _)| k.equals(&key)) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((key, value)),
+        }
+    }
+
+    pub fn insert_keyword(&mut self, key: &str, value: LispType) {
+        self.insert(LispType::Symbol(format!(":{}", key)), value);
+    }
+
+    pub fn equals(&self, other: &LispMap) -> bool {
+        self.entries.len() == other.entries.len()
+            && self.entries.iter().all(|(k, v)| other.get(k).is_some_and(|found| found.equals(v)))
+    }
+
+}
+
+pub fn init_map_funcs(env: Rc<RefCell<Env>>) {
+    decl_native!("hash-map", hash_map_native, "(& kvs)", "Returns a map of the given keys and values", env);
+    decl_native!("get", get_native, "(m key) (m key default)", "Returns the value for key in m, or default (nil) if it is missing", env);
+    decl_native!("assoc", assoc_native, "(m & kvs)", "Returns m with the given keys set to the given values", env);
+    decl_native!("meta", meta_native, "(x)", "Returns the metadata map of a function, map or list, nil if there is none", env);
+    decl_native!("with-meta", with_meta_native, "(x m)", "Returns a copy of the function, map or list x with m as its metadata", env);
+}
+
+fn hash_map_native(args: Vec<LispType>) -> LispResult<LispType> {
+    Ok(LispType::Map(LispMap::from_pairs(args)?))
+}
+
+fn get_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    if args.len() != 2 && args.len() != 3 {
+        return Err(format!("Expected 2 or 3 arguments to \"get\", found {}", args.len()));
+    }
+
+    let default = args.get(2).cloned().unwrap_or(LispType::Nil);
+
+    match &args[0] {
+        LispType::Map(map) => Ok(map.get(&args[1]).cloned().unwrap_or(default)),
+        LispType::Nil => Ok(default),
+        // Tagged literals look up like {:tag tag :form form}
+        LispType::Tagged(tagged) => match &args[1] {
+            LispType::Symbol(key) if key == ":tag" => Ok(LispType::Symbol(tagged.tag.clone())),
+            LispType::Symbol(key) if key == ":form" => Ok((*tagged.form).clone()),
+            _ => Ok(default),
+        },
+        _ => Err(String::from("First argument to \"get\" is not a map")),
+    }
+
+}
+
+fn assoc_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    if args.is_empty() {
+        return Err(String::from("Expected at least 1 argument to \"assoc\", found 0"));
+    }
+
+    let mut iter = args.into_iter();
+
+    let mut map = match iter.next().unwrap() {
+        LispType::Map(map) => map,
+        LispType::Nil => LispMap::new(),
+        _ => return Err(String::from("First argument to \"assoc\" is not a map")),
+    };
+
+    for (key, value) in LispMap::from_pairs(iter.collect())?.entries {
+        map.insert(key, value);
+    }
+
+    Ok(LispType::Map(map))
+
+}
+
+fn meta_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("meta", &args, 1)?;
+
+    match &args[0] {
+        LispType::Func(func) => Ok(LispType::Map(func.meta.clone())),
+        LispType::Map(LispMap { meta: Some(meta), .. }) => Ok(LispType::Map((**meta).clone())),
+        LispType::List(_, Some(meta)) => Ok(LispType::Map((**meta).clone())),
+        _ => Ok(LispType::Nil),
+    }
+
+}
+
+fn with_meta_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("with-meta", &args, 2)?;
+
+    let mut iter = args.into_iter();
+    let value = iter.next().unwrap();
+
+    let meta = match iter.next().unwrap() {
+        LispType::Map(map) => map,
+        LispType::Nil => LispMap::new(),
+        _ => return Err(String::from("Second argument to \"with-meta\" is not a map")),
+    };
+
+    match value {
+        LispType::Func(mut func) => {
+            func.meta = meta;
+            Ok(LispType::Func(func))
+        }
+        LispType::Map(mut map) => {
+            map.meta = Some(Box::new(meta));
+            Ok(LispType::Map(map))
+        }
+        LispType::List(items, _) => Ok(LispType::List(items, Some(Box::new(meta)))),
+        _ => Err(String::from("Metadata can only be attached to functions, maps and lists")),
+    }
+
+}
@@ -0,0 +1,226 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::env::Env;
+use crate::lisp_type::LispType;
+use crate::loader;
+use crate::reader::Reader;
+use crate::result::LispResult;
+
+pub const SEARCH_PATH_VAR: &str = "LISP_RS_PATH";
+
+// Per namespace state, kept on the top-level env of the script and of every loaded module
+#[derive(Clone, Debug)]
+pub struct Namespace {
+    pub name: String,
+    pub aliases: HashMap<String, Rc<RefCell<Env>>>,
+    pub is_module: bool, // Unset for the script's own namespace, which can be renamed by "ns"
+}
+
+impl Namespace {
+
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: String::from(name),
+            aliases: HashMap::new(),
+            is_module: false,
+        }
+    }
+
+    pub fn new_module(name: &str) -> Self {
+        let mut namespace = Self::new(name);
+        namespace.is_module = true;
+        namespace
+    }
+
+}
+
+// Loaded modules and where to look for new ones, kept on the core env
+#[derive(Debug)]
+pub struct Modules {
+    pub search_paths: Vec<PathBuf>,
+    loaded: HashMap<String, Rc<RefCell<Env>>>,
+    loading: Vec<String>,
+}
+
+impl Modules {
+
+    pub fn new(search_paths: Vec<PathBuf>) -> Self {
+        Self {
+            search_paths,
+            loaded: HashMap::new(),
+            loading: Vec::new(),
+        }
+    }
+
+    // app.util is looked up as app/util.lisp under each search path in order
+    fn find_file(&self, name: &str) -> LispResult<PathBuf> {
+
+        let relative: PathBuf = format!("{}.lisp", name.replace('.', "/")).into();
+
+        for dir in &self.search_paths {
+            let path = dir.join(&relative);
+            if path.is_file() {
+                return Ok(path);
+            }
+        }
+
+        Err(format!("Failed to find module \"{}\" as \"{}\" in search path", name, relative.display()))
+
+    }
+
+}
+
+// Search paths given on the command line come first, then the env var, then the current directory
+pub fn search_paths(cli_paths: Vec<PathBuf>) -> Vec<PathBuf> {
+
+    let mut paths = cli_paths;
+
+    if let Some(var) = std::env::var_os(SEARCH_PATH_VAR) {
+        paths.extend(std::env::split_paths(&var));
+    }
+
+    paths.push(PathBuf::from("."));
+
+    paths
+
+}
+
+// Splits util/parse into ("util", "parse"), leaving "/" itself alone
+pub fn split_qualified(symbol: &str) -> Option<(&str, &str)> {
+    match symbol.split_once('/') {
+        Some((ns, name)) if !ns.is_empty() && !name.is_empty() => Some((ns, name)),
+        _ => None,
+    }
+}
+
+fn load(name: &str, env: &Rc<RefCell<Env>>) -> LispResult<Rc<RefCell<Env>>> {
+
+    let modules = env.borrow().modules().ok_or(String::from("Modules can't be loaded in this environment"))?;
+
+    if let Some(loaded) = modules.borrow().loaded.get(name) {
+        return Ok(Rc::clone(loaded));
+    }
+
+    if modules.borrow().loading.iter().any(|loading| loading == name) {
+        return Err(format!("Found circular \"require\" of module \"{}\"", name));
+    }
+
+    let path = modules.borrow().find_file(name)?;
+    let input = fs::read_to_string(&path).map_err(|err| format!("Failed to read module \"{}\": {}", path.display(), err))?;
+
+    let module_env = Rc::new(RefCell::new(Env::new_namespace(Some(Env::root(env)), Namespace::new_module(name))));
+
+    modules.borrow_mut().loading.push(String::from(name));
+    let reader = Reader::new(&input).with_tag_readers(env.borrow().tag_readers());
+    let result = loader::evaluate_reader(reader, Rc::clone(&module_env));
+    modules.borrow_mut().loading.retain(|loading| loading != name);
+    result.map_err(|msg| format!("Failed to load module \"{}\": {}", name, msg))?;
+
+    modules.borrow_mut().loaded.insert(String::from(name), Rc::clone(&module_env));
+
+    Ok(module_env)
+
+}
+
+// (ns app.util (:require (app.other :as other)))
+pub fn ns_form(args: &[LispType], env: Rc<RefCell<Env>>) -> LispResult<LispType> {
+
+    let name = match args.first() {
+        Some(LispType::Symbol(name)) => name,
+        _ => return Err(String::from("Expected name as first argument to \"ns\" declaration")),
+    };
+
+    let ns_env = Env::namespace_env(&env).ok_or(String::from("Found \"ns\" declaration outside of a namespace"))?;
+
+    {
+        let mut ns_env = ns_env.borrow_mut();
+        let namespace = ns_env.namespace_mut().unwrap();
+        // The script's own namespace can be renamed, a module has to match the name it was required by
+        if !namespace.is_module {
+            namespace.name = name.clone();
+        } else if namespace.name != *name {
+            return Err(format!("Found namespace \"{}\" declared in module \"{}\"", name, namespace.name));
+        }
+    }
+
+    for clause in &args[1..] {
+        match clause {
+            LispType::List(clause, ..) if matches!(clause.first(), Some(LispType::Symbol(s)) if s == ":require") => {
+                for spec in &clause[1..] {
+                    require(spec, &env)?;
+                }
+            }
+            _ => return Err(String::from("Expected (:require ...) clauses in \"ns\" declaration")),
+        }
+    }
+
+    Ok(LispType::Nil)
+
+}
+
+// (require app.util (app.other :as other :refer (parse)))
+pub fn require_form(args: &[LispType], env: Rc<RefCell<Env>>) -> LispResult<LispType> {
+
+    for spec in args {
+        require(spec, &env)?;
+    }
+
+    Ok(LispType::Nil)
+
+}
+
+fn require(spec: &LispType, env: &Rc<RefCell<Env>>) -> LispResult<()> {
+
+    let (name, options) = match spec {
+        LispType::Symbol(name) => (name, &[][..]),
+        LispType::List(spec, ..) => match spec.first() {
+            Some(LispType::Symbol(name)) => (name, &spec[1..]),
+            _ => return Err(String::from("Expected module name at the start of \"require\" spec")),
+        },
+        _ => return Err(String::from("Expected module name or list as \"require\" spec")),
+    };
+
+    if options.len() % 2 != 0 {
+        return Err(format!("Found odd number of options in \"require\" spec for \"{}\"", name));
+    }
+
+    let module_env = load(name, env)?;
+    let ns_env = Env::namespace_env(env).ok_or(String::from("Found \"require\" outside of a namespace"))?;
+
+    ns_env.borrow_mut().namespace_mut().unwrap().aliases.insert(name.clone(), Rc::clone(&module_env));
+
+    for option in options.chunks(2) {
+        match (&option[0], &option[1]) {
+            (LispType::Symbol(key), LispType::Symbol(alias)) if key == ":as" => {
+                ns_env.borrow_mut().namespace_mut().unwrap().aliases.insert(alias.clone(), Rc::clone(&module_env));
+            }
+            (LispType::Symbol(key), LispType::Symbol(all)) if key == ":refer" && all == ":all" => {
+                for (symbol, value) in module_env.borrow().public_bindings() {
+                    ns_env.borrow_mut().set(symbol.as_str(), value);
+                }
+            }
+            (LispType::Symbol(key), LispType::List(symbols, ..)) if key == ":refer" => {
+                for symbol in symbols {
+                    let symbol = if let LispType::Symbol(s) = symbol { s } else {
+                        return Err(String::from("Expected symbols in \":refer\" list"));
+                    };
+                    let value = module_env.borrow().find_public(symbol)
+                        .ok_or(format!("Failed to find public symbol \"{}\" in module \"{}\"", symbol, name))?;
+                    ns_env.borrow_mut().set(symbol.as_str(), value);
+                }
+            }
+            _ => return Err(format!("Expected \":as\" or \":refer\" options in \"require\" spec for \"{}\"", name)),
+        }
+    }
+
+    Ok(())
+
+}
+
+pub fn is_private(value: &LispType) -> bool {
+    matches!(value, LispType::Func(func) if func.meta.get_keyword("private").is_some_and(|private| private.is_truthy()))
+}
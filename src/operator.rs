@@ -3,25 +3,43 @@ use std::rc::Rc;
 
 use crate::env::Env;
 use crate::lisp_type::LispType;
-use crate::func::LispFunc;
+use crate::func::{LispFunc, check_arity, decl_native, read_arglists};
 use crate::result::LispResult;
 
 macro_rules! decl_operator {
-    ( $symbol:expr, $func:expr, $env:expr ) => {
-         $env.borrow_mut().set($symbol, LispType::Func(LispFunc::new_operator($func, Rc::clone(&$env))));
+    ( $symbol:expr, $func:expr, $doc:expr, $env:expr ) => {
+        let mut func = LispFunc::new_operator($func, Rc::clone(&$env));
+        func.meta.insert_keyword("name", LispType::Symbol(String::from($symbol)));
+        func.meta.insert_keyword("arglists", read_arglists("(a b)"));
+        func.meta.insert_keyword("doc", LispType::String(String::from($doc)));
+        $env.borrow_mut().set($symbol, LispType::Func(func));
     }
 }
 
 pub fn init_operator_funcs(env: Rc<RefCell<Env>>) {
-    decl_operator!("+", add_symbol, env);
-    decl_operator!("-", sub_symbol, env);
-    decl_operator!("*", mul_symbol, env);
-    decl_operator!("/", div_symbol, env);
-    decl_operator!("=", eq_symbol, env);
-    decl_operator!("<", lt_symbol, env);
-    decl_operator!("<=", lte_symbol, env);
-    decl_operator!(">", gt_symbol, env);
-    decl_operator!(">=", gte_symbol, env);
+    decl_operator!("+", add_symbol, "Adds two numbers, the result is a float if either one is", env);
+    decl_operator!("-", sub_symbol, "Subtracts b from a, the result is a float if either one is", env);
+    decl_operator!("*", mul_symbol, "Multiplies two numbers, the result is a float if either one is", env);
+    decl_operator!("/", div_symbol, "Divides a by b, integer division if both are ints", env);
+    decl_operator!("=", eq_symbol, "Returns true if a and b are equal, both must have the same type", env);
+    decl_operator!("<", lt_symbol, "Returns true if a is less than b", env);
+    decl_operator!("<=", lte_symbol, "Returns true if a is less than or equal to b", env);
+    decl_operator!(">", gt_symbol, "Returns true if a is greater than b", env);
+    decl_operator!(">=", gte_symbol, "Returns true if a is greater than or equal to b", env);
+    decl_native!("doc", doc_native, "(f)", "Prints the name, arguments and docstring of a function", env);
+}
+
+fn doc_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("doc", &args, 1)?;
+
+    match &args[0] {
+        LispType::Func(func) => println!("{}", func.describe()),
+        _ => return Err(String::from("First argument to \"doc\" is not a function")),
+    }
+
+    Ok(LispType::Nil)
+
 }
 
 fn add_symbol(a_in: LispType, b_in: LispType) -> LispResult<LispType> {
@@ -86,11 +104,12 @@ fn lt_symbol(a_in: LispType, b_in: LispType) -> LispResult<LispType> {
         (LispType::Float(a), LispType::Int(b)) => a < (b as f32),
         (LispType::Float(a), LispType::Float(b)) => a < b,
         (LispType::Int(a), LispType::Int(b)) => a < b,
-        (LispType::String(a), LispType::String(b)) => a.len() < b.len(),
-        (LispType::Symbol(a), LispType::Symbol(b)) => a.len() < b.len(),
-        (LispType::Bool(a), LispType::Bool(b)) => a < b,
+        (LispType::String(a), LispType::String(b)) => a < b,
+        (LispType::Char(a), LispType::Char(b)) => a < b,
+        (LispType::Symbol(a), LispType::Symbol(b)) => a < b,
+        (LispType::Bool(a), LispType::Bool(b)) => !a & b,
         (LispType::Nil, LispType::Nil) => return Err(String::from("Can't compare nil values")),
-        (LispType::List(_), LispType::List(_)) => return Err(String::from("Can't compare lists")),
+        (LispType::List(..), LispType::List(..)) => return Err(String::from("Can't compare lists")),
         (LispType::Func(_), LispType::Func(_)) => return Err(String::from("Can't compare functions")),
         (_, _) => return Err(String::from("Failed to compare both elements in lt statement")),
     };
@@ -111,11 +130,12 @@ fn lte_symbol(a_in: LispType, b_in: LispType) -> LispResult<LispType> {
         (LispType::Float(a), LispType::Int(b)) => a <= b as f32,
         (LispType::Float(a), LispType::Float(b)) => a <= b,
         (LispType::Int(a), LispType::Int(b)) => a <= b,
-        (LispType::String(a), LispType::String(b)) => a.len() <= b.len(),
-        (LispType::Symbol(a), LispType::Symbol(b)) => a.len() <= b.len(),
+        (LispType::String(a), LispType::String(b)) => a <= b,
+        (LispType::Char(a), LispType::Char(b)) => a <= b,
+        (LispType::Symbol(a), LispType::Symbol(b)) => a <= b,
         (LispType::Bool(a), LispType::Bool(b)) => a <= b,
         (LispType::Nil, LispType::Nil) => return Err(String::from("Can't compare nil values")),
-        (LispType::List(_), LispType::List(_)) => return Err(String::from("Can't compare lists")),
+        (LispType::List(..), LispType::List(..)) => return Err(String::from("Can't compare lists")),
         (LispType::Func(_), LispType::Func(_)) => return Err(String::from("Can't compare functions")),
         (_, _) => return Err(String::from("Failed to compare both elements in lte statement")),
     };
@@ -135,11 +155,12 @@ fn gt_symbol(a_in: LispType, b_in: LispType) -> LispResult<LispType> {
         (LispType::Float(a), LispType::Int(b)) => a > b as f32,
         (LispType::Float(a), LispType::Float(b)) => a > b,
         (LispType::Int(a), LispType::Int(b)) => a > b,
-        (LispType::String(a), LispType::String(b)) => a.len() > b.len(),
-        (LispType::Symbol(a), LispType::Symbol(b)) => a.len() > b.len(),
-        (LispType::Bool(a), LispType::Bool(b)) => a > b,
+        (LispType::String(a), LispType::String(b)) => a > b,
+        (LispType::Char(a), LispType::Char(b)) => a > b,
+        (LispType::Symbol(a), LispType::Symbol(b)) => a > b,
+        (LispType::Bool(a), LispType::Bool(b)) => a & !b,
         (LispType::Nil, LispType::Nil) => return Err(String::from("Can't compare nil values")),
-        (LispType::List(_), LispType::List(_)) => return Err(String::from("Can't compare lists")),
+        (LispType::List(..), LispType::List(..)) => return Err(String::from("Can't compare lists")),
         (LispType::Func(_), LispType::Func(_)) => return Err(String::from("Can't compare functions")),
         (_, _) => return Err(String::from("Failed to compare both elements in gt statement")),
     };
@@ -159,11 +180,12 @@ fn gte_symbol(a_in: LispType, b_in: LispType) -> LispResult<LispType> {
         (LispType::Float(a), LispType::Int(b)) => a >= b as f32,
         (LispType::Float(a), LispType::Float(b)) => a >= b,
         (LispType::Int(a), LispType::Int(b)) => a >= b,
-        (LispType::String(a), LispType::String(b)) => a.len() >= b.len(),
-        (LispType::Symbol(a), LispType::Symbol(b)) => a.len() >= b.len(),
+        (LispType::String(a), LispType::String(b)) => a >= b,
+        (LispType::Char(a), LispType::Char(b)) => a >= b,
+        (LispType::Symbol(a), LispType::Symbol(b)) => a >= b,
         (LispType::Bool(a), LispType::Bool(b)) => a >= b,
         (LispType::Nil, LispType::Nil) => return Err(String::from("Can't compare nil values")),
-        (LispType::List(_), LispType::List(_)) => return Err(String::from("Can't compare lists")),
+        (LispType::List(..), LispType::List(..)) => return Err(String::from("Can't compare lists")),
         (LispType::Func(_), LispType::Func(_)) => return Err(String::from("Can't compare functions")),
         (_, _) => return Err(String::from("Failed to compare both elements in gte statement")),
     };
@@ -184,10 +206,15 @@ fn eq_symbol(a_in: LispType, b_in: LispType) -> LispResult<LispType> {
         (LispType::Float(a), LispType::Float(b)) => a == b,
         (LispType::Int(a), LispType::Int(b)) => a == b,
         (LispType::String(a), LispType::String(b)) => a == b,
+        (LispType::Char(a), LispType::Char(b)) => a == b,
         (LispType::Bool(a), LispType::Bool(b)) => a == b,
         (LispType::Nil, LispType::Nil) => true,
         (LispType::Symbol(a), LispType::Symbol(b)) => a == b,
-        (LispType::List(_), LispType::List(_)) => return Err(String::from("Can't compare lists")),
+        (LispType::Map(a), LispType::Map(b)) => a.equals(&b),
+        (LispType::Set(a), LispType::Set(b)) => a.equals(&b),
+        (a @ LispType::Tagged(_), b @ LispType::Tagged(_)) => a.equals(&b),
+        (a @ LispType::Vector(_), b @ LispType::Vector(_)) => a.equals(&b),
+        (a @ LispType::List(..), b @ LispType::List(..)) => a.equals(&b),
         (LispType::Func(_), LispType::Func(_)) => return Err(String::from("Can't compare functions")),
         (_, _) => return Err(String::from("Failed to compare both elements in eq statement")),
     };
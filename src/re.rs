@@ -0,0 +1,181 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use regex::{Captures, Regex};
+
+use crate::env::Env;
+use crate::lisp_type::LispType;
+use crate::func::{LispFunc, check_arity, decl_native};
+use crate::map::LispMap;
+use crate::result::LispResult;
+use crate::string;
+
+// Patterns given as strings are compiled once and shared by every regex builtin
+type PatternCache = Rc<RefCell<HashMap<String, Rc<Regex>>>>;
+
+pub fn init_re_funcs(env: Rc<RefCell<Env>>) {
+
+    let cache: PatternCache = Rc::new(RefCell::new(HashMap::new()));
+
+    let patterns = Rc::clone(&cache);
+    decl_native!("re-pattern", move |args| re_pattern_native(args, &patterns), "(s)", "Returns the regex compiled from s", env);
+    let patterns = Rc::clone(&cache);
+    decl_native!("re-find", move |args| re_find_native(args, &patterns), "(re s)",
+        "Returns the first match of re in s, nil if there is none. See re-seq for how matches look", env);
+    let patterns = Rc::clone(&cache);
+    decl_native!("re-matches", move |args| re_matches_native(args, &patterns), "(re s)",
+        "Returns the match of re against the whole of s, nil if it doesn't match all of it", env);
+    let patterns = Rc::clone(&cache);
+    decl_native!("re-seq", move |args| re_seq_native(args, &patterns), "(re s)",
+        "Returns a list of every match of re in s. A match is the matched string, a list of it and its groups \
+         if re has groups, or a map of :match and each named group if re has named groups", env);
+    let patterns = Rc::clone(&cache);
+    decl_native!("re-replace", move |args| re_replace_native(args, &patterns), "(re s replacement)",
+        "Returns s with every match of re replaced by the string replacement, where $1 and $name are groups, \
+         or by the result of calling replacement with the match", env);
+    decl_native!("regex?", is_regex_native, "(x)", "Returns true if x is a regex", env);
+
+}
+
+fn compile(pattern: &str, cache: &PatternCache) -> LispResult<Rc<Regex>> {
+
+    if let Some(regex) = cache.borrow().get(pattern) {
+        return Ok(Rc::clone(regex));
+    }
+
+    let regex = Rc::new(Regex::new(pattern).map_err(|err| format!("Invalid regex \"{}\": {}", pattern, err))?);
+    cache.borrow_mut().insert(String::from(pattern), Rc::clone(&regex));
+
+    Ok(regex)
+
+}
+
+// Builtins take either a regex or a string to compile
+fn expect_regex(name: &str, value: &LispType, cache: &PatternCache) -> LispResult<Rc<Regex>> {
+    match value {
+        LispType::Regex(regex) => Ok(Rc::clone(regex)),
+        LispType::String(pattern) => compile(pattern, cache),
+        _ => Err(format!("First argument to \"{}\" is not a regex or string", name)),
+    }
+}
+
+fn expect_input<'a>(name: &str, value: &'a LispType) -> LispResult<&'a str> {
+    match value {
+        LispType::String(s) => Ok(s),
+        _ => Err(format!("Second argument to \"{}\" is not a string", name)),
+    }
+}
+
+fn group(captures: &Captures, index: usize) -> LispType {
+    match captures.get(index) {
+        Some(m) => LispType::String(String::from(m.as_str())),
+        None => LispType::Nil, // An optional group that didn't take part in the match
+    }
+}
+
+fn match_value(regex: &Regex, captures: &Captures) -> LispType {
+
+    if regex.capture_names().flatten().next().is_some() {
+        let mut map = LispMap::new();
+        map.insert_keyword("match", group(captures, 0));
+        for (index, name) in regex.capture_names().enumerate() {
+            if let Some(name) = name {
+                map.insert_keyword(name, group(captures, index));
+            }
+        }
+        return LispType::Map(map);
+    }
+
+    if captures.len() > 1 {
+        return LispType::List((0..captures.len()).map(|index| group(captures, index)).collect(), None);
+    }
+
+    group(captures, 0)
+
+}
+
+fn re_pattern_native(args: Vec<LispType>, cache: &PatternCache) -> LispResult<LispType> {
+
+    check_arity("re-pattern", &args, 1)?;
+
+    match &args[0] {
+        regex @ LispType::Regex(_) => Ok(regex.clone()),
+        LispType::String(pattern) => Ok(LispType::Regex(compile(pattern, cache)?)),
+        _ => Err(String::from("First argument to \"re-pattern\" is not a string")),
+    }
+
+}
+
+fn re_find_native(args: Vec<LispType>, cache: &PatternCache) -> LispResult<LispType> {
+
+    check_arity("re-find", &args, 2)?;
+
+    let regex = expect_regex("re-find", &args[0], cache)?;
+    let input = expect_input("re-find", &args[1])?;
+
+    match regex.captures(input) {
+        Some(captures) => Ok(match_value(&regex, &captures)),
+        None => Ok(LispType::Nil),
+    }
+
+}
+
+fn re_matches_native(args: Vec<LispType>, cache: &PatternCache) -> LispResult<LispType> {
+
+    check_arity("re-matches", &args, 2)?;
+
+    let regex = expect_regex("re-matches", &args[0], cache)?;
+    let input = expect_input("re-matches", &args[1])?;
+
+    // Anchoring the pattern, rather than checking the span of the first match, means a|ab matches all of "ab"
+    let anchored = compile(&format!("^(?:{})$", regex.as_str()), cache)?;
+
+    match anchored.captures(input) {
+        Some(captures) => Ok(match_value(&anchored, &captures)),
+        None => Ok(LispType::Nil),
+    }
+
+}
+
+fn re_seq_native(args: Vec<LispType>, cache: &PatternCache) -> LispResult<LispType> {
+
+    check_arity("re-seq", &args, 2)?;
+
+    let regex = expect_regex("re-seq", &args[0], cache)?;
+    let input = expect_input("re-seq", &args[1])?;
+
+    Ok(LispType::List(regex.captures_iter(input).map(|captures| match_value(&regex, &captures)).collect(), None))
+
+}
+
+fn re_replace_native(args: Vec<LispType>, cache: &PatternCache) -> LispResult<LispType> {
+
+    check_arity("re-replace", &args, 3)?;
+
+    let regex = expect_regex("re-replace", &args[0], cache)?;
+    let input = expect_input("re-replace", &args[1])?;
+
+    match &args[2] {
+        LispType::String(replacement) => Ok(LispType::String(regex.replace_all(input, replacement.as_str()).into_owned())),
+        LispType::Func(func) => {
+            let mut result = String::new();
+            let mut last = 0;
+            for captures in regex.captures_iter(input) {
+                let whole = captures.get(0).unwrap();
+                result.push_str(&input[last..whole.start()]);
+                result.push_str(&string::display(&func.call_evaluated(vec![match_value(&regex, &captures)])?));
+                last = whole.end();
+            }
+            result.push_str(&input[last..]);
+            Ok(LispType::String(result))
+        }
+        _ => Err(String::from("Third argument to \"re-replace\" is not a string or function")),
+    }
+
+}
+
+fn is_regex_native(args: Vec<LispType>) -> LispResult<LispType> {
+    check_arity("regex?", &args, 1)?;
+    Ok(LispType::Bool(matches!(args[0], LispType::Regex(_))))
+}
@@ -1,142 +1,410 @@
 #![allow(dead_code)]
 
-use crate::{lisp_type::LispType, result::LispResult};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::{lexer::{Lexer, Token, TokenKind}, lisp_type::LispType, map::LispMap, set::LispSet, result::LispResult, tagged::TagReaders};
 
 use regex::Regex;
 
-pub struct Reader {
-    pub tokens: Vec<String>,
-    position: usize,
+// Escapes in string literals, shared with printing so a string reads back as it was written.
+// Any other backslash is kept as written, so "\d+" still works as a pattern for the regex builtins
+pub const STRING_ESCAPES: [(char, char); 4] = [
+    ('n', '\n'),
+    ('t', '\t'),
+    ('"', '"'),
+    ('\\', '\\'),
+];
+
+// Named characters, shared with printing so \newline reads back as it was written
+pub const CHAR_NAMES: [(&str, char); 5] = [
+    ("newline", '\n'),
+    ("space", ' '),
+    ("tab", '\t'),
+    ("return", '\r'),
+    ("nul", '\0'),
+];
+
+// Reads forms straight off the lexer, only ever holding one token of lookahead
+pub struct Reader<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<Token<'a>>,
+    done: bool, // Set after an error, the position in the input can't be trusted anymore
+    in_anon_fn: bool, // Set while reading the body of a #(...) function
+    tag_readers: Option<Rc<RefCell<TagReaders>>>, // Without these every #tag is an error
 }
 
-impl Reader {
+type DispatchFn<'a> = fn(&mut Reader<'a>, Token<'a>) -> LispResult<LispType>;
+
+impl<'a> Reader<'a> {
+
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            lexer: Lexer::new(input),
+            peeked: None,
+            done: false,
+            in_anon_fn: false,
+            tag_readers: None,
+        }
+    }
 
-    pub fn tokenize(input: String) -> Self {
+    pub fn with_tag_readers(mut self, tag_readers: Option<Rc<RefCell<TagReaders>>>) -> Self {
+        self.tag_readers = tag_readers;
+        self
+    }
 
-        let regex = Regex::new(r#"[\s,]*(~@|[\[\]{}()'`~^@]|"(?:\\.|[^\\"])*"?|;.*|[^\s\[\]{}('"`,;)]*)"#).unwrap();
-        let mut tokens: Vec<String> = Vec::new();
-        
-        tokens.push(String::from("("));
-        tokens.push(String::from("do"));
+    // Reader macros that start with "#", keyed on the first character of the token right after it
+    const DISPATCH: [(char, DispatchFn<'a>); 3] = [
+        ('{', Reader::read_set),
+        ('"', Reader::read_regex),
+        ('(', Reader::read_anon_fn),
+    ];
 
-        for (_, [raw_token]) in regex.captures_iter(input.as_str()).map(|c| c.extract()) {
-            
-            if raw_token.trim().is_empty() {
-                continue; 
-            }
+    // Forms commented out with #_ are read and dropped here, so nothing past this sees them
+    fn peek_token(&mut self) -> LispResult<Option<Token<'a>>> {
 
-            if raw_token.chars().nth(0).unwrap() == ';' {
-                continue;
+        while self.peeked.is_none() {
+
+            let token = match self.lexer.next().transpose()? {
+                Some(token) => token,
+                None => return Ok(None),
+            };
+
+            if token.kind != TokenKind::Discard {
+                self.peeked = Some(token);
+                break;
             }
 
-            tokens.push(String::from(raw_token));
+            match self.peek_token()? {
+                None | Some(Token { kind: TokenKind::RParen | TokenKind::RBracket | TokenKind::RBrace, .. }) =>
+                    return Err(format!("Expected form after \"#_\" at {}", token.span)),
+                Some(_) => { self.read_form()?; }
+            }
 
         }
 
-        tokens.push(String::from(")"));
+        Ok(self.peeked)
 
-        Self {
-            tokens,
-            position: 0,
-        }
+    }
 
+    fn next_token(&mut self) -> LispResult<Token<'a>> {
+        self.peek_token()?;
+        self.peeked.take().ok_or(String::from("Unexpected end of input"))
     }
 
-    fn next(&mut self) -> LispResult<String> {
-        if self.position > self.tokens.len() - 1 {
-            return Err(String::from("Attempted to access token out of bounds"));
+    // Byte offset of the first input not yet used by a form. An error with this at the end of the
+    // input means the form was cut short rather than malformed
+    pub fn offset(&self) -> usize {
+        match &self.peeked {
+            Some(token) => token.span.start,
+            None => self.lexer.position(),
         }
-        let token = self.tokens[self.position].clone();
-        self.position += 1;
-        Ok(token)
     }
 
-    fn peek(&self) -> LispResult<String> {
-        if self.position > self.tokens.len() - 1 {
-            return Err(String::from("Attempted to view token out of bounds"));
-        }
-        Ok(self.tokens[self.position].clone())
+    pub fn is_empty(&mut self) -> LispResult<bool> {
+        Ok(self.peek_token()?.is_none())
     }
 
     pub fn read_form(&mut self) -> LispResult<LispType> {
 
-        let token = self.peek()?;
-        let current_char = token.chars().nth(0).unwrap();
+        let token = self.peek_token()?.ok_or(String::from("Unexpected end of input"))?;
 
-        match current_char {
-            '(' => self.read_list(),
-            ')' => Err(String::from("Found list closing token with no opener somehow")),
+        match token.kind {
+            TokenKind::LParen => Ok(LispType::List(self.read_seq(TokenKind::RParen)?, None)),
+            TokenKind::LBracket => Ok(LispType::Vector(self.read_seq(TokenKind::RBracket)?)),
+            TokenKind::LBrace => Ok(LispType::Map(LispMap::from_pairs(self.read_seq(TokenKind::RBrace)?)?)),
+            TokenKind::RParen | TokenKind::RBracket | TokenKind::RBrace => Err(format!("Unexpected \"{}\" at {}", token.text, token.span)),
+            TokenKind::Caret => self.read_meta(),
+            TokenKind::Hash => self.read_dispatch(),
             _ => self.read_atom(),
         }
 
     }
 
-    fn read_list(&mut self) -> LispResult<LispType> {
+    // Reads exactly one form, rejecting anything left over after it
+    pub fn read_only_form(&mut self) -> LispResult<LispType> {
+
+        let form = self.read_form()?;
+
+        if let Some(token) = self.peek_token()? {
+            return Err(format!("Unexpected \"{}\" after form at {}", token.text, token.span));
+        }
+
+        Ok(form)
+
+    }
+
+    // Reads forms up to the closing token, which must be the one matching the opener
+    fn read_seq(&mut self, close: TokenKind) -> LispResult<Vec<LispType>> {
+
+        let opener = self.next_token()?;
+        let close_text = match close {
+            TokenKind::RParen => ")",
+            TokenKind::RBracket => "]",
+            _ => "}",
+        };
 
-        self.next()?; // Should be left paren
-        
         let mut list: Vec<LispType> = Vec::new();
 
         loop {
 
-            let token = self.peek()?;
-            let current_char = token.chars().nth(0).unwrap();
+            let token = match self.peek_token()? {
+                Some(token) => token,
+                None => return Err(format!("Unclosed \"{}\" opened at {}", opener.text, opener.span)),
+            };
 
-            if current_char == ')' {
+            if token.kind == close {
                 break;
             }
-            
+
+            if matches!(token.kind, TokenKind::RParen | TokenKind::RBracket | TokenKind::RBrace) {
+                return Err(format!("Unexpected \"{}\" at {}, expected \"{}\" to close \"{}\" opened at {}",
+                    token.text, token.span, close_text, opener.text, opener.span));
+            }
+
             let value = self.read_form()?;
             list.push(value);
 
         };
 
-        self.next()?; // Should be right paren
+        self.next_token()?; // Should be the closer
+
+        Ok(list)
+
+    }
+
+    // ^{:doc "..."} form and ^:private form read as (with-meta form {...})
+    fn read_meta(&mut self) -> LispResult<LispType> {
+
+        let caret = self.next_token()?;
+
+        if self.is_empty()? {
+            return Err(format!("Expected map or keyword after \"^\" at {}", caret.span));
+        }
+
+        let meta = match self.read_form()? {
+            map @ LispType::Map(_) => map,
+            LispType::Symbol(s) if s.starts_with(':') => {
+                let mut map = LispMap::new();
+                map.insert(LispType::Symbol(s), LispType::Bool(true));
+                LispType::Map(map)
+            }
+            _ => return Err(String::from("Expected map or keyword after \"^\"")),
+        };
+
+        if self.is_empty()? {
+            return Err(format!("Expected form after metadata at {}", caret.span));
+        }
+
+        let form = self.read_form()?;
+
+        Ok(LispType::List(vec![LispType::Symbol(String::from("with-meta")), form, meta], None))
+
+    }
+
+    fn read_dispatch(&mut self) -> LispResult<LispType> {
+
+        let hash = self.next_token()?;
+
+        // The dispatch character has to follow the "#" directly, "# {" is not a set
+        let next = match self.peek_token()? {
+            Some(next) if next.span.start == hash.span.end => next,
+            _ => return Err(format!("Expected dispatch character after \"#\" at {}", hash.span)),
+        };
+
+        let first = next.text.chars().next().unwrap();
+
+        match Self::DISPATCH.iter().find(|(c, _)| *c == first) {
+            Some((_, read)) => read(self, hash),
+            None if next.kind == TokenKind::Symbol => self.read_tagged(hash),
+            None => Err(format!("Unknown dispatch \"#{}\" at {}", next.text, hash.span)),
+        }
+
+    }
+
+    // #inst "2024-01-01" is replaced by whatever the reader function for inst returns
+    fn read_tagged(&mut self, hash: Token<'a>) -> LispResult<LispType> {
+
+        let tag = self.next_token()?.text;
+
+        if self.is_empty()? {
+            return Err(format!("Expected form after tag \"#{}\" at {}", tag, hash.span));
+        }
+
+        let form = self.read_form()?;
+
+        match &self.tag_readers {
+            Some(tag_readers) => TagReaders::read(tag_readers, tag, form),
+            None => Err(format!("Found no reader function for tag \"#{}\"", tag)),
+        }.map_err(|msg| format!("{} at {}", msg, hash.span))
+
+    }
+
+    // #{1 2 3}, duplicates are dropped
+    fn read_set(&mut self, _hash: Token<'a>) -> LispResult<LispType> {
+        Ok(LispType::Set(LispSet::from_items(self.read_seq(TokenKind::RBrace)?)))
+    }
+
+    // #"\d+" is compiled here so evaluating it again doesn't recompile
+    fn read_regex(&mut self, hash: Token<'a>) -> LispResult<LispType> {
+
+        let token = self.next_token()?;
+        let pattern = &token.text[1..token.text.len() - 1];
+
+        Regex::new(pattern)
+            .map(|regex| LispType::Regex(Rc::new(regex)))
+            .map_err(|err| format!("Invalid regex \"{}\" at {}: {}", pattern, hash.span, err))
+
+    }
+
+    // #(+ % 1) reads as (fn* (%1) (+ %1 1)), %2 and up add arguments and %& takes the rest
+    fn read_anon_fn(&mut self, hash: Token<'a>) -> LispResult<LispType> {
+
+        if self.in_anon_fn {
+            return Err(format!("Found nested \"#()\" function at {}", hash.span));
+        }
+
+        self.in_anon_fn = true;
+        let body = self.read_seq(TokenKind::RParen);
+        self.in_anon_fn = false;
+
+        let (mut arity, mut rest) = (0, false);
+        let body = anon_fn_args(LispType::List(body?, None), &mut arity, &mut rest);
+
+        let mut args: Vec<LispType> = (1..=arity).map(|i| LispType::Symbol(format!("%{}", i))).collect();
+        if rest {
+            args.push(LispType::Symbol(String::from("&")));
+            args.push(LispType::Symbol(String::from("%&")));
+        }
+
+        Ok(LispType::List(vec![LispType::Symbol(String::from("fn*")), LispType::List(args, None), body], None))
 
-        Ok(LispType::List(list))
-        
     }
 
     fn read_atom(&mut self) -> LispResult<LispType> {
-        
-        let token = self.next()?;
 
-        let first_char = token.chars().nth(0).unwrap();
-        
-        if first_char.is_numeric() || first_char == '-' {
+        let token = self.next_token()?;
 
-            if token.contains('.') { // float
-                match token.parse::<f32>() {
-                    Ok(f) => return Ok(LispType::Float(f)),
-                    Err(_) => {}
-                }
-            } else {
-                match token.parse::<i32>() {
-                    Ok(i) => return Ok(LispType::Int(i)),
-                    Err(_) => {},
-                }
+        match token.kind {
+
+            TokenKind::Number => {
+                let parsed = if token.text.contains('.') {
+                    token.text.parse::<f32>().ok().map(LispType::Float)
+                } else {
+                    token.text.parse::<i32>().ok().map(LispType::Int)
+                };
+                parsed.ok_or(format!("Invalid number \"{}\" at {}", token.text, token.span))
+            }
+
+            // The lexer has already checked for the closing quote
+            TokenKind::String => Ok(LispType::String(unescape(&token.text[1..token.text.len() - 1]))),
+
+            TokenKind::Char => {
+                let name = &token.text[1..];
+                let mut chars = name.chars();
+                let parsed = match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some(c),
+                    (Some('u'), Some(_)) => u32::from_str_radix(&name[1..], 16).ok().and_then(char::from_u32),
+                    _ => CHAR_NAMES.iter().find(|(n, _)| *n == name).map(|(_, c)| *c),
+                };
+                parsed.map(LispType::Char).ok_or(format!("Unknown character \"{}\" at {}", token.text, token.span))
             }
+
+            _ => match token.text {
+                "true" => Ok(LispType::Bool(true)),
+                "false" => Ok(LispType::Bool(false)),
+                "nil" => Ok(LispType::Nil),
+                _ => Ok(LispType::Symbol(String::from(token.text))),
+            },
+
+        }
+
+    }
+
+}
+
+// Yields one top-level form at a time so each can be evaluated before the next is read.
+// Reading stops after the first error since the position in the input can't be trusted
+impl Iterator for Reader<'_> {
+
+    type Item = LispResult<LispType>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+
+        if self.done {
+            return None;
         }
-        
-        // Unwrapping last char should be safe as it uses current length to get it
-        // Does >= 2 to confirm that it isn't just a single quote being read so it can
-        // be passed to symbol processing if it's just one
-        if first_char == '"' && token.ends_with('"') && token.len() >= 2 {
-            let mut str = token.clone();
-            str.remove(0);
-            str.remove(str.len() - 1);
-            return Ok(LispType::String(str));
+
+        let form = match self.is_empty() {
+            Ok(true) => return None,
+            Ok(false) => self.read_form(),
+            Err(msg) => Err(msg),
+        };
+
+        if form.is_err() {
+            self.done = true;
         }
 
-        match token.as_str() {
-            "true" => return Ok(LispType::Bool(true)),
-            "false" => return Ok(LispType::Bool(false)),
-            "nil" => return Ok(LispType::Nil),
-            _ => {}
+        Some(form)
+
+    }
+
+}
+
+fn unescape(text: &str) -> String {
+
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
         }
+        match chars.next() {
+            Some(escaped) => match STRING_ESCAPES.iter().find(|(name, _)| *name == escaped) {
+                Some((_, unescaped)) => result.push(*unescaped),
+                None => {
+                    result.push('\\');
+                    result.push(escaped);
+                }
+            },
+            None => result.push('\\'),
+        }
+    }
+
+    result
 
-        Ok(LispType::Symbol(token))
+}
+
+// Renames % to %1 throughout a #() body, recording the highest %n used and whether %& is
+fn anon_fn_args(form: LispType, arity: &mut usize, rest: &mut bool) -> LispType {
 
+    match form {
+        LispType::Symbol(s) if s == "%" => {
+            *arity = (*arity).max(1);
+            LispType::Symbol(String::from("%1"))
+        }
+        LispType::Symbol(s) if s == "%&" => {
+            *rest = true;
+            LispType::Symbol(s)
+        }
+        LispType::Symbol(s) => {
+            if let Some(n) = s.strip_prefix('%').and_then(|n| n.parse::<usize>().ok()).filter(|n| *n > 0) {
+                *arity = (*arity).max(n);
+            }
+            LispType::Symbol(s)
+        }
+        LispType::List(items, ..) => LispType::List(items.into_iter().map(|item| anon_fn_args(item, arity, rest)).collect(), None),
+        LispType::Vector(items) => LispType::Vector(items.into_iter().map(|item| anon_fn_args(item, arity, rest)).collect()),
+        LispType::Map(mut map) => {
+            map.entries = map.entries.into_iter()
+                .map(|(k, v)| (anon_fn_args(k, arity, rest), anon_fn_args(v, arity, rest)))
+                .collect();
+            LispType::Map(map)
+        }
+        LispType::Set(set) => LispType::Set(LispSet::from_items(set.items.into_iter().map(|item| anon_fn_args(item, arity, rest)).collect())),
+        other => other,
     }
 
 }
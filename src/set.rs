@@ -0,0 +1,74 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::env::Env;
+use crate::lisp_type::LispType;
+use crate::func::{LispFunc, check_arity, decl_native};
+use crate::result::LispResult;
+
+#[derive(Clone, Debug, Default)]
+pub struct LispSet {
+    pub items: Vec<LispType>, // Kept in insertion order, compared with LispType::equals
+}
+
+impl LispSet {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_items(items: Vec<LispType>) -> Self {
+
+        let mut set = Self::new();
+
+        for item in items {
+            set.insert(item);
+        }
+
+        set
+
+    }
+
+    pub fn contains(&self, item: &LispType) -> bool {
+        self.items.iter().any(|i| i.equals(item))
+    }
+
+    pub fn insert(&mut self, item: LispType) {
+        if !self.contains(&item) {
+            self.items.push(item);
+        }
+    }
+
+    pub fn equals(&self, other: &LispSet) -> bool {
+        self.items.len() == other.items.len() && self.items.iter().all(|item| other.contains(item))
+    }
+
+}
+
+pub fn init_set_funcs(env: Rc<RefCell<Env>>) {
+    decl_native!("hash-set", hash_set_native, "(& items)", "Returns a set of the given items", env);
+    decl_native!("set?", is_set_native, "(x)", "Returns true if x is a set", env);
+    decl_native!("contains?", contains_native, "(coll x)", "Returns true if the set coll has x, or the map coll has x as a key", env);
+}
+
+fn hash_set_native(args: Vec<LispType>) -> LispResult<LispType> {
+    Ok(LispType::Set(LispSet::from_items(args)))
+}
+
+fn is_set_native(args: Vec<LispType>) -> LispResult<LispType> {
+    check_arity("set?", &args, 1)?;
+    Ok(LispType::Bool(matches!(args[0], LispType::Set(_))))
+}
+
+fn contains_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("contains?", &args, 2)?;
+
+    match &args[0] {
+        LispType::Set(set) => Ok(LispType::Bool(set.contains(&args[1]))),
+        LispType::Map(map) => Ok(LispType::Bool(map.get(&args[1]).is_some())),
+        LispType::Nil => Ok(LispType::Bool(false)),
+        _ => Err(String::from("First argument to \"contains?\" is not a set or map")),
+    }
+
+}
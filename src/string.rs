@@ -0,0 +1,307 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::env::Env;
+use crate::lisp_type::LispType;
+use crate::func::{LispFunc, check_arity, decl_native};
+use crate::result::LispResult;
+
+// Strings are indexed and measured in unicode scalar values, never bytes
+pub fn init_string_funcs(env: Rc<RefCell<Env>>) {
+    decl_native!("string?", is_string_native, "(x)", "Returns true if x is a string", env);
+    decl_native!("str", str_native, "(& xs)", "Returns the concatenation of xs printed without quotes, nil prints as nothing", env);
+    decl_native!("format", format_native, "(fmt & args)", "Returns fmt with each {} or ~a replaced by the next argument printed as by str", env);
+    decl_native!("subs", subs_native, "(s start) (s start end)", "Returns the characters of s from start up to but not including end", env);
+    decl_native!("split", split_native, "(s sep)", "Returns a list of the parts of s between each occurrence of the string or regex sep", env);
+    decl_native!("join", join_native, "(xs) (sep xs)", "Returns the items of xs printed as by str, with sep between them", env);
+    decl_native!("trim", trim_native, "(s)", "Returns s without whitespace at either end", env);
+    decl_native!("upper-case", upper_case_native, "(s)", "Returns s with every character in upper case", env);
+    decl_native!("lower-case", lower_case_native, "(s)", "Returns s with every character in lower case", env);
+    decl_native!("index-of", index_of_native, "(s sub)", "Returns the index of the first occurrence of sub in s, nil if there is none", env);
+    decl_native!("replace", replace_native, "(s match replacement)", "Returns s with every occurrence of match replaced", env);
+    decl_native!("starts-with?", starts_with_native, "(s prefix)", "Returns true if s starts with prefix", env);
+    decl_native!("ends-with?", ends_with_native, "(s suffix)", "Returns true if s ends with suffix", env);
+    decl_native!("includes?", includes_native, "(s sub)", "Returns true if sub occurs anywhere in s", env);
+    decl_native!("string->number", string_to_number_native, "(s)", "Returns the int or float written in s, nil if it is not a number", env);
+    decl_native!("number->string", number_to_string_native, "(n)", "Returns the int or float n written as a string", env);
+    decl_native!("char", char_native, "(s)", "Returns the only character of the one character string s", env);
+    decl_native!("char?", is_char_native, "(x)", "Returns true if x is a character", env);
+    decl_native!("char->int", char_to_int_native, "(c)", "Returns the unicode code point of c", env);
+    decl_native!("int->char", int_to_char_native, "(n)", "Returns the character with unicode code point n", env);
+    decl_native!("string->list", string_to_list_native, "(s)", "Returns a list of the characters in s", env);
+}
+
+fn char_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("char", &args, 1)?;
+
+    match &args[0] {
+        c @ LispType::Char(_) => Ok(c.clone()),
+        LispType::String(s) => {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(LispType::Char(c)),
+                _ => Err(format!("Expected one character string as argument to \"char\", found \"{}\"", s)),
+            }
+        }
+        _ => Err(String::from("First argument to \"char\" is not a string")),
+    }
+
+}
+
+fn is_char_native(args: Vec<LispType>) -> LispResult<LispType> {
+    check_arity("char?", &args, 1)?;
+    Ok(LispType::Bool(matches!(args[0], LispType::Char(_))))
+}
+
+fn char_to_int_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("char->int", &args, 1)?;
+
+    match &args[0] {
+        LispType::Char(c) => Ok(LispType::Int(*c as i32)),
+        _ => Err(String::from("First argument to \"char->int\" is not a character")),
+    }
+
+}
+
+fn int_to_char_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("int->char", &args, 1)?;
+
+    match &args[0] {
+        LispType::Int(n) => u32::try_from(*n).ok().and_then(char::from_u32)
+            .map(LispType::Char)
+            .ok_or(format!("Found invalid code point {} in \"int->char\"", n)),
+        _ => Err(String::from("First argument to \"int->char\" is not an int")),
+    }
+
+}
+
+fn string_to_list_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("string->list", &args, 1)?;
+
+    match &args[0] {
+        LispType::String(s) => Ok(LispType::List(s.chars().map(LispType::Char).collect(), None)),
+        _ => Err(String::from("First argument to \"string->list\" is not a string")),
+    }
+
+}
+
+const ORDINALS: [&str; 3] = ["First", "Second", "Third"];
+
+fn expect_string<'a>(name: &str, args: &'a [LispType], index: usize) -> LispResult<&'a str> {
+    match &args[index] {
+        LispType::String(s) => Ok(s),
+        _ => Err(format!("{} argument to \"{}\" is not a string", ORDINALS[index], name)),
+    }
+}
+
+fn expect_index(name: &str, args: &[LispType], index: usize) -> LispResult<usize> {
+    match &args[index] {
+        LispType::Int(i) if *i >= 0 => Ok(*i as usize),
+        _ => Err(format!("{} argument to \"{}\" is not a non-negative int", ORDINALS[index], name)),
+    }
+}
+
+// How "str" prints a value, strings and characters without their quotes or backslash
+pub fn display(value: &LispType) -> String {
+    match value {
+        LispType::String(s) => s.clone(),
+        LispType::Char(c) => c.to_string(),
+        LispType::Nil => String::new(),
+        _ => value.to_string(),
+    }
+}
+
+fn is_string_native(args: Vec<LispType>) -> LispResult<LispType> {
+    check_arity("string?", &args, 1)?;
+    Ok(LispType::Bool(matches!(args[0], LispType::String(_))))
+}
+
+fn str_native(args: Vec<LispType>) -> LispResult<LispType> {
+    Ok(LispType::String(args.iter().map(display).collect()))
+}
+
+fn format_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    if args.is_empty() {
+        return Err(String::from("Expected at least 1 argument to \"format\", found 0"));
+    }
+
+    let fmt = expect_string("format", &args, 0)?;
+    let mut values = args[1..].iter();
+    let mut result = String::new();
+    let mut rest = fmt;
+
+    while let Some(at) = rest.find(['{', '~']) {
+
+        result.push_str(&rest[..at]);
+        rest = &rest[at..];
+
+        if rest.starts_with("{}") || rest.starts_with("~a") {
+            let value = values.next().ok_or(format!("Found more placeholders than arguments in \"format\" string \"{}\"", fmt))?;
+            result.push_str(&display(value));
+            rest = &rest[2..];
+        } else {
+            result.push_str(&rest[..1]);
+            rest = &rest[1..];
+        }
+
+    }
+
+    result.push_str(rest);
+
+    if values.next().is_some() {
+        return Err(format!("Found more arguments than placeholders in \"format\" string \"{}\"", fmt));
+    }
+
+    Ok(LispType::String(result))
+
+}
+
+fn subs_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    if args.len() != 2 && args.len() != 3 {
+        return Err(format!("Expected 2 or 3 arguments to \"subs\", found {}", args.len()));
+    }
+
+    let s = expect_string("subs", &args, 0)?;
+    let length = s.chars().count();
+    let start = expect_index("subs", &args, 1)?;
+    let end = if args.len() == 3 { expect_index("subs", &args, 2)? } else { length };
+
+    if start > end || end > length {
+        return Err(format!("Found out of range indices {} and {} in \"subs\" on string of length {}", start, end, length));
+    }
+
+    Ok(LispType::String(s.chars().skip(start).take(end - start).collect()))
+
+}
+
+fn split_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("split", &args, 2)?;
+
+    let s = expect_string("split", &args, 0)?;
+
+    let parts: Vec<&str> = match &args[1] {
+        LispType::String(sep) if sep.is_empty() => return Err(String::from("Found empty separator in \"split\"")),
+        LispType::String(sep) => s.split(sep.as_str()).collect(),
+        LispType::Regex(regex) => regex.split(s).collect(),
+        _ => return Err(String::from("Second argument to \"split\" is not a string or regex")),
+    };
+
+    Ok(LispType::List(parts.into_iter().map(|part| LispType::String(String::from(part))).collect(), None))
+
+}
+
+fn join_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    let (sep, items) = match args.as_slice() {
+        [items] => (String::new(), items),
+        [sep, items] => (display(sep), items),
+        _ => return Err(format!("Expected 1 or 2 arguments to \"join\", found {}", args.len())),
+    };
+
+    let items = match items {
+        LispType::List(items, ..) => items.iter().map(display).collect::<Vec<String>>(),
+        LispType::Nil => Vec::new(),
+        _ => return Err(String::from("Expected list argument to \"join\"")),
+    };
+
+    Ok(LispType::String(items.join(&sep)))
+
+}
+
+fn trim_native(args: Vec<LispType>) -> LispResult<LispType> {
+    check_arity("trim", &args, 1)?;
+    Ok(LispType::String(String::from(expect_string("trim", &args, 0)?.trim())))
+}
+
+fn upper_case_native(args: Vec<LispType>) -> LispResult<LispType> {
+    check_arity("upper-case", &args, 1)?;
+    Ok(LispType::String(expect_string("upper-case", &args, 0)?.to_uppercase()))
+}
+
+fn lower_case_native(args: Vec<LispType>) -> LispResult<LispType> {
+    check_arity("lower-case", &args, 1)?;
+    Ok(LispType::String(expect_string("lower-case", &args, 0)?.to_lowercase()))
+}
+
+fn index_of_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("index-of", &args, 2)?;
+
+    let s = expect_string("index-of", &args, 0)?;
+    let sub = expect_string("index-of", &args, 1)?;
+
+    // find gives a byte offset, the index is counted in characters
+    match s.find(sub) {
+        Some(at) => Ok(LispType::Int(s[..at].chars().count() as i32)),
+        None => Ok(LispType::Nil),
+    }
+
+}
+
+fn replace_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("replace", &args, 3)?;
+
+    let s = expect_string("replace", &args, 0)?;
+    let from = expect_string("replace", &args, 1)?;
+    let to = expect_string("replace", &args, 2)?;
+
+    if from.is_empty() {
+        return Err(String::from("Found empty match in \"replace\""));
+    }
+
+    Ok(LispType::String(s.replace(from, to)))
+
+}
+
+fn starts_with_native(args: Vec<LispType>) -> LispResult<LispType> {
+    check_arity("starts-with?", &args, 2)?;
+    Ok(LispType::Bool(expect_string("starts-with?", &args, 0)?.starts_with(expect_string("starts-with?", &args, 1)?)))
+}
+
+fn ends_with_native(args: Vec<LispType>) -> LispResult<LispType> {
+    check_arity("ends-with?", &args, 2)?;
+    Ok(LispType::Bool(expect_string("ends-with?", &args, 0)?.ends_with(expect_string("ends-with?", &args, 1)?)))
+}
+
+fn includes_native(args: Vec<LispType>) -> LispResult<LispType> {
+    check_arity("includes?", &args, 2)?;
+    Ok(LispType::Bool(expect_string("includes?", &args, 0)?.contains(expect_string("includes?", &args, 1)?)))
+}
+
+// Accepts numbers the way the reader writes them, so "1.5" is a float and "1e5" is not a number
+fn string_to_number_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("string->number", &args, 1)?;
+
+    let s = expect_string("string->number", &args, 0)?.trim();
+
+    let parsed = if !s.chars().any(|c| c.is_ascii_digit()) {
+        None
+    } else if s.contains('.') {
+        s.parse::<f32>().ok().map(LispType::Float)
+    } else {
+        s.parse::<i32>().ok().map(LispType::Int)
+    };
+
+    Ok(parsed.unwrap_or(LispType::Nil))
+
+}
+
+fn number_to_string_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("number->string", &args, 1)?;
+
+    match &args[0] {
+        n @ (LispType::Int(_) | LispType::Float(_)) => Ok(LispType::String(n.to_string())),
+        _ => Err(String::from("First argument to \"number->string\" is not a number")),
+    }
+
+}
@@ -0,0 +1,149 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::env::Env;
+use crate::lisp_type::LispType;
+use crate::func::{LispFunc, NativeFunc, check_arity, decl_native};
+use crate::result::LispResult;
+
+// Functions that turn the form after #tag into a value, kept on the core env and
+// handed to each Reader
+#[derive(Debug, Default)]
+pub struct TagReaders {
+    handlers: HashMap<String, LispType>,
+    default: Option<LispType>, // Called with the tag and form for tags that have no handler
+}
+
+impl TagReaders {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, tag: &str, handler: LispType) {
+        self.handlers.insert(String::from(tag), handler);
+    }
+
+    pub fn register_native(&mut self, tag: &str, handler: Rc<NativeFunc>) {
+        let mut func = LispFunc::new_native(handler);
+        func.meta.insert_keyword("name", LispType::Symbol(format!("#{}", tag)));
+        self.register(tag, LispType::Func(func));
+    }
+
+    // Unknown tags are an error unless a default handler has been set. The handler is cloned out
+    // first so it can register tag readers itself
+    pub fn read(tag_readers: &Rc<RefCell<TagReaders>>, tag: &str, form: LispType) -> LispResult<LispType> {
+
+        let (handler, default) = {
+            let tag_readers = tag_readers.borrow();
+            (tag_readers.handlers.get(tag).cloned(), tag_readers.default.clone())
+        };
+
+        match (handler, default) {
+            (Some(LispType::Func(handler)), _) => handler.call_evaluated(vec![form]),
+            (None, Some(LispType::Func(default))) => default.call_evaluated(vec![LispType::Symbol(String::from(tag)), form]),
+            _ => Err(format!("Found no reader function for tag \"#{}\"", tag)),
+        }
+
+    }
+
+}
+
+// A tagged literal kept as data, printed back the way it was read
+#[derive(Clone, Debug)]
+pub struct TaggedLiteral {
+    pub tag: String,
+    pub form: Box<LispType>,
+}
+
+pub fn init_tagged_funcs(env: Rc<RefCell<Env>>) {
+
+    let tag_readers = env.borrow().tag_readers().expect("Core env has no tag readers");
+
+    tag_readers.borrow_mut().register_native("uuid", Rc::new(uuid_reader));
+
+    let readers = Rc::clone(&tag_readers);
+    decl_native!("register-tag-reader", move |args| register_tag_reader_native(args, &readers), "(tag f)",
+        "Makes the reader call f with the form after #tag and use its result in place of both", env);
+    let readers = Rc::clone(&tag_readers);
+    decl_native!("set-default-tag-reader", move |args| set_default_tag_reader_native(args, &readers), "(f)",
+        "Makes the reader call f with the tag and form for tags with no reader function, nil restores the error", env);
+    decl_native!("tagged-literal", tagged_literal_native, "(tag form)",
+        "Returns a tagged literal that keeps tag and form as data, usable as the default tag reader", env);
+    decl_native!("tagged-literal?", is_tagged_literal_native, "(x)", "Returns true if x is a tagged literal", env);
+
+}
+
+fn tag_name(value: &LispType, func_name: &str) -> LispResult<String> {
+    match value {
+        LispType::Symbol(s) | LispType::String(s) => Ok(String::from(s.trim_start_matches(['#', ':']))),
+        _ => Err(format!("First argument to \"{}\" is not a symbol or string", func_name)),
+    }
+}
+
+fn register_tag_reader_native(args: Vec<LispType>, readers: &Rc<RefCell<TagReaders>>) -> LispResult<LispType> {
+
+    check_arity("register-tag-reader", &args, 2)?;
+
+    let tag = tag_name(&args[0], "register-tag-reader")?;
+
+    if !matches!(args[1], LispType::Func(_)) {
+        return Err(String::from("Second argument to \"register-tag-reader\" is not a function"));
+    }
+
+    readers.borrow_mut().register(&tag, args[1].clone());
+
+    Ok(LispType::Nil)
+
+}
+
+fn set_default_tag_reader_native(args: Vec<LispType>, readers: &Rc<RefCell<TagReaders>>) -> LispResult<LispType> {
+
+    check_arity("set-default-tag-reader", &args, 1)?;
+
+    readers.borrow_mut().default = match &args[0] {
+        func @ LispType::Func(_) => Some(func.clone()),
+        LispType::Nil => None,
+        _ => return Err(String::from("First argument to \"set-default-tag-reader\" is not a function or nil")),
+    };
+
+    Ok(LispType::Nil)
+
+}
+
+fn tagged_literal_native(args: Vec<LispType>) -> LispResult<LispType> {
+
+    check_arity("tagged-literal", &args, 2)?;
+
+    let tag = tag_name(&args[0], "tagged-literal")?;
+
+    Ok(LispType::Tagged(TaggedLiteral { tag, form: Box::new(args[1].clone()) }))
+
+}
+
+fn is_tagged_literal_native(args: Vec<LispType>) -> LispResult<LispType> {
+    check_arity("tagged-literal?", &args, 1)?;
+    Ok(LispType::Bool(matches!(args[0], LispType::Tagged(_))))
+}
+
+// #uuid "f81d4fae-7dec-11d0-a765-00a0c91e6bf6" checks the format and reads as the string
+fn uuid_reader(args: Vec<LispType>) -> LispResult<LispType> {
+
+    let uuid = match args.first() {
+        Some(LispType::String(s)) => s,
+        _ => return Err(String::from("Expected string after \"#uuid\"")),
+    };
+
+    let valid = uuid.len() == 36 && uuid.char_indices().all(|(i, c)| match i {
+        8 | 13 | 18 | 23 => c == '-',
+        _ => c.is_ascii_hexdigit(),
+    });
+
+    if !valid {
+        return Err(format!("Found invalid uuid \"{}\"", uuid));
+    }
+
+    Ok(LispType::String(uuid.clone()))
+
+}